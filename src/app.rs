@@ -1,44 +1,237 @@
-use crate::challenge::Challenge;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    challenge::{Challenge, ChallengeDetails, Writeup},
+    cli::SearchArgs,
+    curriculum::{LearningPath, Stage},
+    downloads::DownloadEvent,
+    errors::CoreError,
+    form::SearchForm,
+    progress::{self, ProgressStatus, ProgressStore},
+    rating::Ratings,
+    recommend::{Recommendation, Recommender},
+    roulette,
+    stats::Stats,
+    view::{SortKey, SortOrder, build_view},
+};
+
+// Start fetching the next page when the selection gets this close to the end.
+const LOAD_MORE_THRESHOLD: usize = 5;
+// Length of the Recommended tab.
+const RECOMMENDATIONS: usize = 20;
+// Errors kept in the Errors tab, older ones are dropped.
+const ERROR_LOG_SIZE: usize = 50;
+
+/// Where the writeup browser currently stands. Writeups are spoilers, so the
+/// content is only fetched once the reveal has been confirmed.
+#[derive(Debug, PartialEq)]
+pub enum WriteupStage {
+    List,
+    ConfirmReveal,
+    Reading,
+}
+
+pub struct WriteupBrowser {
+    pub challenge_url: String,
+    pub selected_index: usize,
+    pub stage: WriteupStage,
+    pub content: Option<String>,
+    pub scroll: u16,
+    pub should_fetch: bool,
+    pub should_download: bool,
+}
+
+pub enum DownloadStatus {
+    Queued,
+    Downloading { attempt: u32 },
+    Done(String),
+    Failed,
+    Cancelled,
+}
+
+/// A download as shown in the downloads panel.
+pub struct DownloadItem {
+    pub id: usize,
+    pub name: String,
+    pub challenge: Challenge,
+    pub status: DownloadStatus,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+impl DownloadItem {
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status,
+            DownloadStatus::Queued | DownloadStatus::Downloading { .. }
+        )
+    }
+}
+
+/// An error as shown in the popup and the Errors tab.
+pub struct LoggedError {
+    pub time: u64,
+    /// What the app was doing when it failed.
+    pub context: String,
+    pub message: String,
+    pub details: Vec<(&'static str, String)>,
+}
+
+/// Top level views of the TUI, switched with Tab.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tab {
+    Challenges,
+    Recommended,
+    Paths,
+    Stats,
+    Errors,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 5] = [
+        Tab::Challenges,
+        Tab::Recommended,
+        Tab::Paths,
+        Tab::Stats,
+        Tab::Errors,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Challenges => "Challenges",
+            Tab::Recommended => "Recommended",
+            Tab::Paths => "Paths",
+            Tab::Stats => "Stats",
+            Tab::Errors => "Errors",
+        }
+    }
+}
 
 pub struct App {
+    pub tab: Tab,
+    /// Every loaded challenge, in search result order.
     pub challenges: Vec<Challenge>,
+    /// Indices into `challenges` of the visible rows, after filter and sort.
+    pub view: Vec<usize>,
+    /// Position of the selection in `view`.
     pub selected_index: usize,
     pub status_message: String,
     pub should_quit: bool,
     pub scroll_offset: usize,
     pub should_download: bool,
+    pub pages_loaded: usize,
+    pub has_more_pages: bool,
+    pub should_load_more: bool,
+    pub show_details: bool,
+    pub details: HashMap<String, ChallengeDetails>,
+    pub failed_details: HashSet<String>,
+    pub writeup_browser: Option<WriteupBrowser>,
+    pub downloads: Vec<DownloadItem>,
+    pub selected_download: usize,
+    pub show_downloads: bool,
+    pub cancel_request: Option<usize>,
+    pub search_form: SearchForm,
+    pub show_search_form: bool,
+    pub should_search: bool,
+    pub filter: String,
+    pub editing_filter: bool,
+    pub sort: Option<SortOrder>,
+    pub progress: ProgressStore,
+    /// Derived from `progress` whenever it changes, not on every frame.
+    pub ratings: Ratings,
+    pub stats: Stats,
+    pub selected_recommendation: usize,
+    pub paths: Vec<LearningPath>,
+    /// Position of the selection among the stages of every path.
+    pub selected_stage: usize,
+    /// URLs of a stage to load into the challenge list.
+    pub stage_to_load: Option<Vec<String>>,
+    /// Pick a random challenge, weighted by quality when true.
+    pub random_request: Option<bool>,
+    /// Latest errors first.
+    pub errors: VecDeque<LoggedError>,
+    pub selected_error: usize,
+    /// Whether the latest error is shown on top of everything else.
+    pub show_error_popup: bool,
 }
 
 impl App {
     pub fn new() -> Self {
         Self {
+            tab: Tab::Challenges,
             challenges: Vec::new(),
+            view: Vec::new(),
             selected_index: 0,
             status_message: String::from("Loading challenges..."),
             should_quit: false,
             scroll_offset: 0,
             should_download: false,
+            pages_loaded: 0,
+            has_more_pages: false,
+            should_load_more: false,
+            show_details: false,
+            details: HashMap::new(),
+            failed_details: HashSet::new(),
+            writeup_browser: None,
+            downloads: Vec::new(),
+            selected_download: 0,
+            show_downloads: false,
+            cancel_request: None,
+            search_form: SearchForm::from_args(&SearchArgs::default()),
+            show_search_form: false,
+            should_search: false,
+            filter: String::new(),
+            editing_filter: false,
+            sort: None,
+            progress: ProgressStore::default(),
+            ratings: Ratings::compute(&ProgressStore::default()),
+            stats: Stats::default(),
+            selected_recommendation: 0,
+            paths: Vec::new(),
+            selected_stage: 0,
+            stage_to_load: None,
+            random_request: None,
+            errors: VecDeque::new(),
+            selected_error: 0,
+            show_error_popup: false,
         }
     }
 
     pub fn next_challenge(&mut self) {
-        if !self.challenges.is_empty() {
-            self.selected_index = (self.selected_index + 1) % self.challenges.len();
+        if !self.view.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.view.len();
         }
+        self.request_more_if_needed();
     }
 
     pub fn previous_challenge(&mut self) {
-        if !self.challenges.is_empty() {
+        if !self.view.is_empty() {
             if self.selected_index == 0 {
-                self.selected_index = self.challenges.len() - 1;
+                self.selected_index = self.view.len() - 1;
             } else {
                 self.selected_index -= 1;
             }
         }
+        self.request_more_if_needed();
     }
 
     pub fn get_selected_challenge(&self) -> Option<&Challenge> {
-        self.challenges.get(self.selected_index)
+        self.view
+            .get(self.selected_index)
+            .and_then(|&index| self.challenges.get(index))
+    }
+
+    /// Challenges in the order they are displayed.
+    pub fn visible_challenges(&self) -> impl Iterator<Item = &Challenge> {
+        self.view.iter().map(|&index| &self.challenges[index])
+    }
+
+    pub fn next_tab(&mut self) {
+        let index = Tab::ALL
+            .iter()
+            .position(|&tab| tab == self.tab)
+            .unwrap_or(0);
+        self.tab = Tab::ALL[(index + 1) % Tab::ALL.len()];
     }
 
     pub fn quit(&mut self) {
@@ -47,8 +240,12 @@ impl App {
 
     pub fn set_challenges(&mut self, challenges: Vec<Challenge>) {
         self.challenges = challenges;
+        self.view = build_view(&self.challenges, &self.filter, self.sort);
         self.selected_index = 0;
         self.scroll_offset = 0;
+        self.pages_loaded = 0;
+        self.has_more_pages = false;
+        self.should_load_more = false;
         if self.challenges.is_empty() {
             self.status_message = String::from("No challenges found");
         } else {
@@ -56,10 +253,123 @@ impl App {
         }
     }
 
+    /// Appends a freshly fetched results page to the list.
+    pub fn append_page(&mut self, challenges: Vec<Challenge>, page: usize, has_more: bool) {
+        self.challenges.extend(challenges);
+        self.refresh_view();
+        self.set_pagination(page, has_more);
+        self.status_message = format!("Loaded page {}", page);
+    }
+
+    pub fn set_pagination(&mut self, page: usize, has_more: bool) {
+        self.pages_loaded = page;
+        self.has_more_pages = has_more;
+        self.should_load_more = false;
+    }
+
+    pub fn page_summary(&self) -> String {
+        let summary = format!(
+            "page {}, {} loaded",
+            self.pages_loaded,
+            self.challenges.len()
+        );
+        if self.view.len() == self.challenges.len() {
+            summary
+        } else {
+            format!("{}, {} shown", summary, self.view.len())
+        }
+    }
+
+    pub fn start_filter(&mut self) {
+        self.editing_filter = true;
+    }
+
+    /// Keeps the current filter and goes back to the list.
+    pub fn finish_filter(&mut self) {
+        self.editing_filter = false;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.editing_filter = false;
+        self.filter.clear();
+        self.refresh_view();
+    }
+
+    pub fn push_filter_char(&mut self, c: char) {
+        self.filter.push(c);
+        self.refresh_view();
+    }
+
+    pub fn pop_filter_char(&mut self) {
+        self.filter.pop();
+        self.refresh_view();
+    }
+
+    /// Sorts by `key`, flipping the direction when it is already the sort key.
+    pub fn toggle_sort(&mut self, key: SortKey) {
+        self.sort = match self.sort {
+            Some(sort) if sort.key == key => Some(SortOrder {
+                key,
+                descending: !sort.descending,
+            }),
+            _ => Some(SortOrder {
+                key,
+                descending: false,
+            }),
+        };
+        self.refresh_view();
+        self.status_message = match self.sort {
+            Some(SortOrder {
+                key,
+                descending: true,
+            }) => format!("Sorted by {}, descending", key.label()),
+            _ => format!("Sorted by {}", key.label()),
+        };
+    }
+
+    // Rebuilds the view, keeping the selection on the same challenge if it is
+    // still visible
+    fn refresh_view(&mut self) {
+        let selected = self.view.get(self.selected_index).copied();
+        self.view = build_view(&self.challenges, &self.filter, self.sort);
+        self.selected_index = selected
+            .and_then(|selected| self.view.iter().position(|&index| index == selected))
+            .unwrap_or(0);
+    }
+
     pub fn set_status(&mut self, message: String) {
         self.status_message = message;
     }
 
+    /// Shows `error` in a popup and keeps it in the Errors tab. `context` says
+    /// what failed, such as "Failed to load challenges".
+    pub fn report_error(&mut self, context: &str, error: &CoreError) {
+        self.status_message = format!("{}: {}", context, error);
+        self.errors.push_front(LoggedError {
+            time: progress::now(),
+            context: context.to_string(),
+            message: error.to_string(),
+            details: error.details(),
+        });
+        self.errors.truncate(ERROR_LOG_SIZE);
+        self.selected_error = 0;
+        self.show_error_popup = true;
+    }
+
+    pub fn dismiss_error(&mut self) {
+        self.show_error_popup = false;
+    }
+
+    pub fn next_error(&mut self) {
+        if self.selected_error + 1 < self.errors.len() {
+            self.selected_error += 1;
+        }
+    }
+
+    pub fn previous_error(&mut self) {
+        self.selected_error = self.selected_error.saturating_sub(1);
+    }
+
     pub fn trigger_download(&mut self) {
         self.should_download = true;
     }
@@ -67,4 +377,384 @@ impl App {
     pub fn reset_download_flag(&mut self) {
         self.should_download = false;
     }
+
+    pub fn finish_loading_more(&mut self) {
+        self.should_load_more = false;
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    /// Cached details of the selected challenge, if they were fetched already.
+    pub fn get_selected_details(&self) -> Option<&ChallengeDetails> {
+        self.get_selected_challenge()
+            .and_then(|challenge| self.details.get(&challenge.url))
+    }
+
+    /// URL of the selected challenge when the detail pane needs it fetched.
+    /// A failed fetch is only remembered while its challenge stays selected,
+    /// selecting it again retries.
+    pub fn details_to_fetch(&mut self) -> Option<String> {
+        if !self.show_details && self.writeup_browser.is_none() {
+            return None;
+        }
+        let url = self.get_selected_challenge()?.url.clone();
+        self.failed_details.retain(|failed| *failed == url);
+        Some(url)
+            .filter(|url| !self.details.contains_key(url) && !self.failed_details.contains(url))
+    }
+
+    pub fn cache_details(&mut self, url: String, details: ChallengeDetails) {
+        self.details.insert(url, details);
+    }
+
+    pub fn mark_details_failed(&mut self, url: String) {
+        self.failed_details.insert(url);
+    }
+
+    pub fn open_writeups(&mut self) {
+        if let Some(challenge) = self.get_selected_challenge() {
+            let url = challenge.url.clone();
+            // Opening the writeups again retries a failed fetch
+            self.failed_details.remove(&url);
+            self.writeup_browser = Some(WriteupBrowser {
+                challenge_url: url,
+                selected_index: 0,
+                stage: WriteupStage::List,
+                content: None,
+                scroll: 0,
+                should_fetch: false,
+                should_download: false,
+            });
+        }
+    }
+
+    pub fn close_writeups(&mut self) {
+        self.writeup_browser = None;
+    }
+
+    /// Writeups listed on the page of the challenge the browser was opened for.
+    pub fn get_writeups(&self) -> &[Writeup] {
+        self.writeup_browser
+            .as_ref()
+            .and_then(|browser| self.details.get(&browser.challenge_url))
+            .map(|details| details.writeup_links.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn get_selected_writeup(&self) -> Option<&Writeup> {
+        let browser = self.writeup_browser.as_ref()?;
+        self.get_writeups().get(browser.selected_index)
+    }
+
+    pub fn next_writeup(&mut self) {
+        let count = self.get_writeups().len();
+        if let Some(browser) = self.writeup_browser.as_mut()
+            && count > 0
+        {
+            browser.selected_index = (browser.selected_index + 1) % count;
+        }
+    }
+
+    pub fn previous_writeup(&mut self) {
+        let count = self.get_writeups().len();
+        if let Some(browser) = self.writeup_browser.as_mut()
+            && count > 0
+        {
+            browser.selected_index = (browser.selected_index + count - 1) % count;
+        }
+    }
+
+    /// Asks for confirmation before showing the selected writeup.
+    pub fn request_reveal(&mut self) {
+        let has_writeup = self.get_selected_writeup().is_some();
+        if let Some(browser) = self.writeup_browser.as_mut()
+            && has_writeup
+        {
+            browser.stage = WriteupStage::ConfirmReveal;
+        }
+    }
+
+    pub fn confirm_reveal(&mut self) {
+        if let Some(browser) = self.writeup_browser.as_mut() {
+            browser.stage = WriteupStage::Reading;
+            browser.content = None;
+            browser.scroll = 0;
+            browser.should_fetch = true;
+        }
+    }
+
+    /// Goes back to the writeup list, hiding any revealed content.
+    pub fn hide_writeup(&mut self) {
+        if let Some(browser) = self.writeup_browser.as_mut() {
+            browser.stage = WriteupStage::List;
+            browser.content = None;
+        }
+    }
+
+    pub fn set_writeup_content(&mut self, content: String) {
+        if let Some(browser) = self.writeup_browser.as_mut() {
+            browser.content = Some(content);
+            browser.should_fetch = false;
+        }
+    }
+
+    pub fn scroll_writeup(&mut self, lines: i16) {
+        if let Some(browser) = self.writeup_browser.as_mut() {
+            browser.scroll = browser.scroll.saturating_add_signed(lines);
+        }
+    }
+
+    pub fn trigger_writeup_download(&mut self) {
+        if let Some(browser) = self.writeup_browser.as_mut() {
+            browser.should_download = true;
+        }
+    }
+
+    /// Adds a freshly queued download to the downloads panel.
+    pub fn add_download(&mut self, id: usize, challenge: Challenge) {
+        self.status_message = format!("Queued {}", challenge.name);
+        self.downloads.push(DownloadItem {
+            id,
+            name: challenge.name.clone(),
+            challenge,
+            status: DownloadStatus::Queued,
+            downloaded: 0,
+            total: None,
+        });
+        self.show_downloads = true;
+    }
+
+    pub fn apply_download_event(&mut self, event: DownloadEvent) {
+        let id = match &event {
+            DownloadEvent::Started { id, .. }
+            | DownloadEvent::Progress { id, .. }
+            | DownloadEvent::Finished { id, .. }
+            | DownloadEvent::Failed { id, .. }
+            | DownloadEvent::Cancelled { id } => *id,
+        };
+        let Some(item) = self.downloads.iter_mut().find(|item| item.id == id) else {
+            return;
+        };
+
+        match event {
+            DownloadEvent::Started { attempt, .. } => {
+                item.status = DownloadStatus::Downloading { attempt };
+                item.downloaded = 0;
+            }
+            DownloadEvent::Progress {
+                downloaded, total, ..
+            } => {
+                item.downloaded = downloaded;
+                item.total = total;
+            }
+            DownloadEvent::Finished { outcome, .. } => {
+                self.status_message = outcome.summary();
+                item.status = DownloadStatus::Done(outcome.summary());
+                self.progress
+                    .mark_downloaded(&item.challenge, progress::now());
+                self.refresh_progress();
+                if let Err(error) = self.progress.save() {
+                    self.report_error("Failed to save progress", &error);
+                }
+            }
+            DownloadEvent::Failed { error, .. } => {
+                item.status = DownloadStatus::Failed;
+                let context = format!("Failed to download {}", item.name);
+                self.report_error(&context, &error);
+            }
+            DownloadEvent::Cancelled { .. } => {
+                self.status_message = format!("Cancelled {}", item.name);
+                item.status = DownloadStatus::Cancelled;
+            }
+        }
+    }
+
+    pub fn toggle_downloads(&mut self) {
+        self.show_downloads = !self.show_downloads;
+    }
+
+    pub fn next_download(&mut self) {
+        if !self.downloads.is_empty() {
+            self.selected_download = (self.selected_download + 1) % self.downloads.len();
+        }
+    }
+
+    pub fn previous_download(&mut self) {
+        if !self.downloads.is_empty() {
+            let count = self.downloads.len();
+            self.selected_download = (self.selected_download + count - 1) % count;
+        }
+    }
+
+    /// Asks the main loop to cancel the selected download if it is running.
+    pub fn cancel_selected_download(&mut self) {
+        if let Some(item) = self.downloads.get(self.selected_download)
+            && item.is_active()
+        {
+            self.cancel_request = Some(item.id);
+        }
+    }
+
+    pub fn set_progress(&mut self, progress: ProgressStore) {
+        self.progress = progress;
+        self.refresh_progress();
+    }
+
+    fn refresh_progress(&mut self) {
+        self.ratings = Ratings::compute(&self.progress);
+        self.stats = Stats::compute(&self.progress, progress::now());
+    }
+
+    /// Changes the status of the selected challenge and saves it right away.
+    pub fn set_selected_progress(&mut self, status: ProgressStatus) {
+        let Some(challenge) = self.get_selected_challenge().cloned() else {
+            return;
+        };
+        self.progress
+            .set_status(&challenge, status, progress::now());
+        self.refresh_progress();
+        match self.progress.save() {
+            Ok(()) => {
+                self.status_message = format!("Marked {} as {}", challenge.name, status.label())
+            }
+            Err(error) => self.report_error("Failed to save progress", &error),
+        }
+    }
+
+    /// Best challenges to try next among the loaded ones.
+    pub fn recommendations(&self) -> (Recommender, Vec<Recommendation>) {
+        let recommender = Recommender::new(&self.progress);
+        let recommendations = recommender.rank(&self.challenges, &self.progress, RECOMMENDATIONS);
+        (recommender, recommendations)
+    }
+
+    pub fn next_recommendation(&mut self) {
+        let count = self.recommendations().1.len();
+        if count > 0 {
+            self.selected_recommendation = (self.selected_recommendation + 1) % count;
+        }
+    }
+
+    pub fn previous_recommendation(&mut self) {
+        let count = self.recommendations().1.len();
+        if count > 0 {
+            self.selected_recommendation = (self.selected_recommendation + count - 1) % count;
+        }
+    }
+
+    /// Selects the highlighted recommendation in the challenge list.
+    pub fn show_recommendation(&mut self) {
+        let Some(recommendation) = self
+            .recommendations()
+            .1
+            .into_iter()
+            .nth(self.selected_recommendation)
+        else {
+            return;
+        };
+        self.filter.clear();
+        self.refresh_view();
+        if let Some(position) = self
+            .view
+            .iter()
+            .position(|&index| index == recommendation.index)
+        {
+            self.selected_index = position;
+        }
+        self.tab = Tab::Challenges;
+    }
+
+    /// Every stage of every path, with the index of its path.
+    pub fn stages(&self) -> impl Iterator<Item = (usize, usize, &Stage)> {
+        self.paths
+            .iter()
+            .enumerate()
+            .flat_map(|(path_index, path)| {
+                path.stages
+                    .iter()
+                    .enumerate()
+                    .map(move |(stage_index, stage)| (path_index, stage_index, stage))
+            })
+    }
+
+    pub fn next_stage(&mut self) {
+        let count = self.stages().count();
+        if count > 0 {
+            self.selected_stage = (self.selected_stage + 1) % count;
+        }
+    }
+
+    pub fn previous_stage(&mut self) {
+        let count = self.stages().count();
+        if count > 0 {
+            self.selected_stage = (self.selected_stage + count - 1) % count;
+        }
+    }
+
+    /// Loads the challenges of the selected stage into the challenge list,
+    /// once the previous stages are done.
+    pub fn open_selected_stage(&mut self) {
+        let Some((path_index, stage_index, stage)) = self.stages().nth(self.selected_stage) else {
+            return;
+        };
+        let progress = self.paths[path_index].progress(&self.progress);
+        if !progress[stage_index].unlocked {
+            self.status_message = String::from("Complete the previous stages first");
+            return;
+        }
+
+        if let Some(filter) = &stage.filter {
+            self.search_form = SearchForm::from_params(filter.to_search_params(String::new()));
+            self.should_search = true;
+        } else {
+            self.stage_to_load = Some(stage.challenge_urls());
+        }
+        self.filter.clear();
+        self.tab = Tab::Challenges;
+    }
+
+    pub fn request_random(&mut self, weighted: bool) {
+        self.random_request = Some(weighted);
+    }
+
+    /// Selects a random visible challenge we never touched.
+    pub fn pick_random(&mut self, weighted: bool) {
+        let visible: Vec<&Challenge> = self.visible_challenges().collect();
+        let mut rng = fastrand::Rng::new();
+        match roulette::pick(&visible, &self.progress, weighted, &mut rng) {
+            Some(position) => {
+                self.status_message = format!("Picked {}", visible[position].name);
+                self.selected_index = position;
+            }
+            None => {
+                self.status_message =
+                    String::from("Every listed challenge is already solved or downloaded");
+            }
+        }
+    }
+
+    pub fn open_search_form(&mut self) {
+        self.show_search_form = true;
+    }
+
+    pub fn close_search_form(&mut self) {
+        self.show_search_form = false;
+    }
+
+    /// Closes the form and asks the main loop to run the new search.
+    pub fn submit_search(&mut self) {
+        self.show_search_form = false;
+        self.should_search = true;
+    }
+
+    fn request_more_if_needed(&mut self) {
+        if self.has_more_pages
+            && !self.should_load_more
+            && self.selected_index + LOAD_MORE_THRESHOLD >= self.view.len()
+        {
+            self.should_load_more = true;
+        }
+    }
 }
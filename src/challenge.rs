@@ -1,15 +1,34 @@
-use std::{fs::File, io::Write};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use std::io::BufWriter;
 
+use reqwest::{
+    StatusCode,
+    header::{CONTENT_RANGE, RANGE},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
 use crate::{
-    errors::CoreError,
-    search::{Arch, Language, Platform},
+    errors::{CoreError, Resource},
+    extract::{ExtractOptions, ExtractReport, extract_archive},
+    paths::{discriminated_path, ensure_within, sanitize_file_name, settle_path},
+    search::{Arch, Language, Platform, send, url_on},
+    source::ChallengeSource,
+    workspace::{Workspace, WorkspaceOptions},
 };
 
-const BASE_URL: &str = "https://crackmes.one/";
+/// Quality of a challenge nobody rated, such as a local one without a sidecar
+/// `quality`.
+pub const UNRATED: f32 = 0.0;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Challenge {
     pub language: Language,
     pub author: String,
@@ -19,21 +38,581 @@ pub struct Challenge {
     pub difficulty: f32,
     pub quality: f32,
     pub platform: Platform,
+    /// Upload date as listed in the search results.
+    #[serde(default)]
+    pub upload_date: Option<String>,
+    /// Number of writeups as listed in the search results.
+    #[serde(default)]
+    pub writeups: Option<u32>,
+}
+
+/// Extra information only available on the challenge page itself.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChallengeDetails {
+    pub description: Option<String>,
+    pub upload_date: Option<String>,
+    pub downloads: Option<u32>,
+    pub writeups: Option<u32>,
+    pub comments: Vec<ChallengeComment>,
+    pub hash: Option<String>,
+    pub writeup_links: Vec<Writeup>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeComment {
+    pub author: String,
+    pub text: String,
+}
+
+/// A community writeup attached to a challenge. Writeups contain solutions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Writeup {
+    pub author: String,
+    pub url: String,
+}
+
+impl ChallengeDetails {
+    /// SHA-256 published on the page, if it looks like one.
+    pub fn sha256(&self) -> Option<&str> {
+        self.hash
+            .as_deref()
+            .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+}
+
+impl Writeup {
+    pub fn get_extension(&self) -> &str {
+        self.url
+            .rsplit('/')
+            .next()
+            .and_then(|file| file.rsplit_once('.'))
+            .map(|(_, extension)| extension)
+            .unwrap_or("txt")
+    }
+
+    /// Where the writeup of `challenge` is written in `directory`, before
+    /// `settle` gives it its final name.
+    pub fn destination(
+        &self,
+        challenge: &Challenge,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        let path = discriminated_path(
+            directory,
+            &self.file_stem(challenge),
+            challenge.id(),
+            &self.file_extension(),
+        );
+        ensure_within(directory, &path)?;
+        Ok(path)
+    }
+
+    /// Renames the writeup written at `destination`, see `settle_path`.
+    pub fn settle(&self, challenge: &Challenge, written: &Path) -> Result<PathBuf, CoreError> {
+        settle_path(written, &self.file_stem(challenge), &self.file_extension())
+    }
+
+    fn file_stem(&self, challenge: &Challenge) -> String {
+        format!("{}-writeup-{}", challenge.name, self.author)
+    }
+
+    fn file_extension(&self) -> String {
+        format!(".{}", sanitize_file_name(self.get_extension()))
+    }
+
+    /// Downloads the writeup into `directory` and returns where it was written.
+    pub async fn download(
+        &self,
+        base_url: &str,
+        challenge: &Challenge,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        let path = self.destination(challenge, directory)?;
+
+        let url = url_on(base_url, &self.url);
+        let resp = send(Resource::Writeup, reqwest::Client::new().get(&url)).await?;
+        let body = resp
+            .bytes()
+            .await
+            .map_err(CoreError::request(Resource::Writeup, &url))?;
+        let mut out = BufWriter::new(File::create(&path)?);
+        out.write_all(&body)?;
+        out.flush()?;
+        drop(out);
+        self.settle(challenge, &path)
+    }
+}
+
+impl Challenge {
+    /// Stable identifier of the challenge, the last segment of its URL.
+    pub fn id(&self) -> &str {
+        self.url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.url)
+    }
+
+    /// Where the site serves the archive of the challenge.
+    pub fn get_download_url(&self, base_url: &str) -> String {
+        format!("{}/static{}.zip", base_url, self.url)
+    }
+
+    /// File name of the archive, safe to use on any file system.
+    pub fn archive_name(&self) -> String {
+        format!("{}.zip", sanitize_file_name(&self.name))
+    }
+
+    /// Streams the challenge archive to `destination`, reporting the bytes
+    /// written so far and the expected total to `progress`, and returns the
+    /// SHA-256 of the archive.
+    ///
+    /// Data is first written to `<destination>.part`, which a later call
+    /// resumes with a range request, also after `cancelled` got set.
+    pub async fn download(
+        &self,
+        base_url: &str,
+        destination: &Path,
+        progress: &impl Fn(u64, Option<u64>),
+        cancelled: &AtomicBool,
+    ) -> Result<String, CoreError> {
+        let part = part_path(destination);
+        let existing = tokio::fs::metadata(&part)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+
+        let url = self.get_download_url(base_url);
+        let mut request = reqwest::Client::new().get(&url);
+        if existing > 0 {
+            request = request.header(RANGE, format!("bytes={}-", existing));
+        }
+        let mut resp = match send(Resource::Archive, request).await {
+            Err(CoreError::Status { status, .. })
+                if status == StatusCode::RANGE_NOT_SATISFIABLE =>
+            {
+                // The partial file is unusable, start over on the next attempt
+                tokio::fs::remove_file(&part).await?;
+                return Err(CoreError::Status {
+                    resource: Resource::Archive,
+                    url,
+                    status,
+                });
+            }
+            resp => resp?,
+        };
+
+        let mut hasher = Sha256::new();
+        let mut resumed = existing > 0 && resp.status() == StatusCode::PARTIAL_CONTENT;
+        if resumed && content_range_start(&resp) != Some(existing) {
+            // Appending any other range would corrupt the archive, start over
+            resp = send(Resource::Archive, reqwest::Client::new().get(&url)).await?;
+            resumed = false;
+        }
+        let mut out = if resumed {
+            hash_file(&part, &mut hasher).await?;
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part)
+                .await?
+        } else {
+            tokio::fs::File::create(&part).await?
+        };
+
+        let mut downloaded = if resumed { existing } else { 0 };
+        let total = resp.content_length().map(|length| length + downloaded);
+        progress(downloaded, total);
+
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(CoreError::request(Resource::Archive, &url))?
+        {
+            if cancelled.load(Ordering::Relaxed) {
+                // The partial file stays for the next attempt to resume
+                out.flush().await?;
+                return Err(CoreError::Cancelled);
+            }
+            hasher.update(&chunk);
+            out.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            progress(downloaded, total);
+        }
+
+        out.flush().await?;
+        drop(out);
+        tokio::fs::rename(&part, destination).await?;
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Downloads the challenge into the output directory, laying out its
+    /// workspace and extracting the archive as requested by `options`.
+    ///
+    /// Nothing is ever written outside of `options.output_dir`.
+    pub async fn download_with(
+        &self,
+        source: &dyn ChallengeSource,
+        options: &DownloadOptions,
+        expected_sha256: Option<&str>,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        cancelled: &AtomicBool,
+    ) -> Result<DownloadOutcome, CoreError> {
+        let root = &options.output_dir;
+        fs::create_dir_all(root)?;
+
+        let workspace = match &options.workspace {
+            Some(workspace_options) => {
+                let (root, challenge, workspace_options) =
+                    (root.clone(), self.clone(), workspace_options.clone());
+                Some(
+                    blocking(move || Workspace::create(&root, &challenge, &workspace_options))
+                        .await?,
+                )
+            }
+            None => None,
+        };
+        let archive = match &workspace {
+            Some(workspace) => workspace.original_dir().join(self.archive_name()),
+            // Another challenge may share the name, keep both archives
+            None => discriminated_path(root, &self.name, self.id(), ".zip"),
+        };
+        ensure_within(root, &archive)?;
+
+        let sha256 = source.download(self, &archive, progress, cancelled).await?;
+        if let Some(expected) = expected_sha256
+            && !expected.eq_ignore_ascii_case(&sha256)
+        {
+            let _ = fs::remove_file(&archive);
+            return Err(CoreError::HashMismatch {
+                expected: expected.to_string(),
+                actual: sha256,
+            });
+        }
+        let archive = match &workspace {
+            Some(_) => archive,
+            None => {
+                let name = self.name.clone();
+                let archive = blocking(move || settle_path(&archive, &name, ".zip")).await?;
+                ensure_within(root, &archive)?;
+                archive
+            }
+        };
+
+        let extracted = match &options.extract {
+            Some(extract_options) => {
+                let destination = match &workspace {
+                    Some(workspace) => workspace.original_dir(),
+                    None => archive.with_extension(""),
+                };
+                let (archive, extract_options) = (archive.clone(), extract_options.clone());
+                Some(
+                    blocking(move || extract_archive(&archive, &destination, &extract_options))
+                        .await?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(DownloadOutcome {
+            archive,
+            sha256,
+            verified: expected_sha256.is_some(),
+            workspace,
+            extracted,
+        })
+    }
 }
 
+#[cfg(test)]
 impl Challenge {
-    fn get_download_url(&self) -> String {
-        format!("{}/static{}.zip", BASE_URL, self.url)
+    /// A C/C++ x86-64 Unix challenge named after its `id`, for tests to
+    /// override the fields they care about.
+    pub fn sample(id: &str) -> Self {
+        Self {
+            language: Language::Ccpp,
+            author: String::from("bob"),
+            name: String::from(id),
+            url: format!("/crackme/{}", id),
+            arch: Arch::X8664,
+            difficulty: 2.0,
+            quality: 4.0,
+            platform: Platform::Unix,
+            upload_date: None,
+            writeups: None,
+        }
+    }
+}
+
+// Runs file system heavy work, such as extracting an archive, off the async
+// runtime
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, CoreError> + Send + 'static,
+) -> Result<T, CoreError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|error| CoreError::Io(std::io::Error::other(error)))?
+}
+
+/// What to do with a challenge archive once it is downloaded.
+pub struct DownloadOptions {
+    /// Root directory of every download.
+    pub output_dir: PathBuf,
+    pub extract: Option<ExtractOptions>,
+    pub workspace: Option<WorkspaceOptions>,
+}
+
+pub struct DownloadOutcome {
+    pub archive: PathBuf,
+    pub sha256: String,
+    /// Whether `sha256` matched the hash published on the challenge page.
+    pub verified: bool,
+    pub workspace: Option<Workspace>,
+    pub extracted: Option<ExtractReport>,
+}
+
+impl DownloadOutcome {
+    /// One line summary, for the TUI status bar.
+    pub fn summary(&self) -> String {
+        let summary = self.location_summary();
+        if self.verified {
+            format!("{} (SHA-256 verified)", summary)
+        } else {
+            summary
+        }
+    }
+
+    fn location_summary(&self) -> String {
+        match (&self.workspace, &self.extracted) {
+            (Some(workspace), Some(report)) => format!(
+                "Workspace ready in {} ({} files extracted)",
+                workspace.directory.display(),
+                report.files.len()
+            ),
+            (Some(workspace), None) => {
+                format!("Workspace ready in {}", workspace.directory.display())
+            }
+            (None, Some(report)) => format!(
+                "Extracted {} files to {}",
+                report.files.len(),
+                report.directory.display()
+            ),
+            (None, None) => format!("Successfully downloaded {}", self.archive.display()),
+        }
     }
+}
+
+fn part_path(destination: &Path) -> PathBuf {
+    let mut part = destination.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
 
-    pub async fn download(&self) -> Result<(), CoreError> {
-        let resp = reqwest::get(self.get_download_url())
+// First byte of a 206 response, from `Content-Range: bytes <start>-<end>/<size>`
+fn content_range_start(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub async fn file_sha256(path: &Path) -> Result<String, CoreError> {
+    let mut hasher = Sha256::new();
+    hash_file(path, &mut hasher).await?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<(), CoreError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{CHALLENGE_ID, MockServer, Route, archive};
+
+    fn recorded_challenge() -> Challenge {
+        Challenge {
+            language: Language::Rust,
+            name: String::from("easy_keygen"),
+            difficulty: 2.5,
+            ..Challenge::sample(CHALLENGE_ID)
+        }
+    }
+
+    #[test]
+    fn test_part_path_appends_suffix() {
+        assert_eq!(
+            part_path(Path::new("out/crackme.zip")),
+            PathBuf::from("out/crackme.zip.part")
+        );
+    }
+
+    #[test]
+    fn test_writeups_of_namesakes_do_not_overwrite_each_other() {
+        let directory = tempfile::tempdir().unwrap();
+        let writeup = Writeup {
+            author: String::from("alice"),
+            url: String::from("/writeup/1/solution.md"),
+        };
+        let first = recorded_challenge();
+        let mut namesake = recorded_challenge();
+        namesake.url = String::from("/crackme/other");
+
+        let save = |challenge: &Challenge, content: &str| {
+            let path = writeup.destination(challenge, directory.path()).unwrap();
+            fs::write(&path, content).unwrap();
+            writeup.settle(challenge, &path).unwrap()
+        };
+
+        let path = save(&first, "first");
+        assert_eq!(save(&first, "first"), path);
+        let other = save(&namesake, "namesake");
+        assert_ne!(other, path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&other).unwrap(), "namesake");
+    }
+
+    #[tokio::test]
+    async fn test_download_fetches_the_archive() {
+        let server = MockServer::crackmes().await;
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("easy_keygen.zip");
+
+        let sha256 = recorded_challenge()
+            .download(
+                &server.url(),
+                &destination,
+                &|_, _| {},
+                &AtomicBool::new(false),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&destination).unwrap(), archive());
+        assert_eq!(sha256, file_sha256(&destination).await.unwrap());
+        assert!(!part_path(&destination).exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_a_partial_archive() {
+        let server = MockServer::crackmes().await;
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("easy_keygen.zip");
+        let archive = archive();
+        fs::write(part_path(&destination), &archive[..10]).unwrap();
+
+        let sha256 = recorded_challenge()
+            .download(
+                &server.url(),
+                &destination,
+                &|_, _| {},
+                &AtomicBool::new(false),
+            )
             .await
-            .map_err(|_| CoreError::DownloadFailure)?;
-        let body = resp.bytes().await.map_err(|_| CoreError::DownloadFailure)?;
-        let out =
-            File::create(format!("{}.zip", self.name)).map_err(|_| CoreError::DownloadFailure)?;
-        let _ = BufWriter::new(out).write_all(&body);
-        Ok(())
+            .unwrap();
+        assert_eq!(fs::read(&destination).unwrap(), archive);
+        assert_eq!(sha256, file_sha256(&destination).await.unwrap());
+        assert_eq!(server.requests()[0].header("range"), Some("bytes=10-"));
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_on_a_foreign_range() {
+        let archive = archive();
+        let path = format!("/static/crackme/{}.zip", CHALLENGE_ID);
+        let range = format!("bytes 0-{}/{}", archive.len() - 1, archive.len());
+        let server = MockServer::start(vec![
+            Route::new("GET", &path, archive.clone()).header("Content-Range", &range),
+        ])
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("easy_keygen.zip");
+        fs::write(part_path(&destination), &archive[..10]).unwrap();
+
+        recorded_challenge()
+            .download(
+                &server.url(),
+                &destination,
+                &|_, _| {},
+                &AtomicBool::new(false),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&destination).unwrap(), archive);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("range"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_download_keeps_the_partial_archive() {
+        let server = MockServer::crackmes().await;
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("easy_keygen.zip");
+
+        let result = recorded_challenge()
+            .download(
+                &server.url(),
+                &destination,
+                &|_, _| {},
+                &AtomicBool::new(true),
+            )
+            .await;
+        assert!(matches!(result, Err(CoreError::Cancelled)));
+        assert!(part_path(&destination).exists());
+        assert!(!destination.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_fails_on_missing_archive() {
+        let server = MockServer::crackmes().await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut missing = recorded_challenge();
+        missing.url = String::from("/crackme/missing");
+
+        let result = missing
+            .download(
+                &server.url(),
+                &dir.path().join("missing.zip"),
+                &|_, _| {},
+                &AtomicBool::new(false),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(CoreError::Status {
+                resource: Resource::Archive,
+                status: StatusCode::NOT_FOUND,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hash_file_matches_known_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc");
+        fs::write(&path, b"abc").unwrap();
+
+        let mut hasher = Sha256::new();
+        hash_file(&path, &mut hasher).await.unwrap();
+        assert_eq!(
+            format!("{:x}", hasher.finalize()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
     }
 }
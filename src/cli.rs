@@ -1,10 +1,226 @@
-use crate::search::{Arch, Language, Platform, SearchParams};
-use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use crate::{
+    challenge::DownloadOptions,
+    config::Config,
+    extract::{DEFAULT_PASSWORD, ExtractOptions},
+    roulette::RANDOM_PAGES,
+    search::{Arch, Language, Platform, SearchParams},
+    workspace::WorkspaceOptions,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(name = "cracked")]
 #[command(about = "Browse and download crackmes from crackmes.one", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Filters for the default `tui` command
+    #[command(flatten)]
+    pub search: SearchArgs,
+
+    #[command(flatten)]
+    pub download: DownloadArgs,
+
+    #[command(flatten)]
+    pub index: IndexArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Browse challenges in the terminal UI (default)
+    Tui {
+        #[command(flatten)]
+        search: SearchArgs,
+
+        #[command(flatten)]
+        download: DownloadArgs,
+
+        #[command(flatten)]
+        index: IndexArgs,
+    },
+
+    /// Search challenges and print them without entering the TUI
+    Search {
+        #[command(flatten)]
+        search: SearchArgs,
+
+        /// Maximum number of result pages to fetch (all pages by default)
+        #[arg(long)]
+        pages: Option<usize>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        index: IndexArgs,
+    },
+
+    /// Add the latest challenges to the local index used by --offline
+    Sync {
+        /// Walk every result page instead of stopping at known challenges
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Crawl the whole catalogue into a directory, to browse it without internet
+    Mirror {
+        /// Directory of the mirror, created if needed
+        directory: PathBuf,
+
+        /// Challenges fetched at the same time
+        #[arg(long, default_value_t = 2)]
+        concurrency: usize,
+
+        /// Minimum delay between two requests to crackmes.one, in milliseconds
+        #[arg(long, default_value_t = 1000)]
+        delay: u64,
+    },
+
+    /// Recommend challenges to try next, based on what you solved
+    Recommend {
+        #[command(flatten)]
+        search: SearchArgs,
+
+        /// Number of result pages to pick recommendations from
+        #[arg(long, default_value_t = 3)]
+        pages: usize,
+
+        /// Number of challenges to recommend
+        #[arg(short = 'c', long, default_value_t = 10)]
+        count: usize,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Pick a random challenge you have not solved or downloaded yet
+    Random {
+        #[command(flatten)]
+        search: SearchArgs,
+
+        /// Number of result pages to pick from
+        #[arg(long, default_value_t = RANDOM_PAGES)]
+        pages: usize,
+
+        /// Favour challenges of better quality
+        #[arg(short, long)]
+        weighted: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Print the details of a challenge
+    Info {
+        /// Challenge ID, as found at the end of its crackmes.one URL, or the
+        /// URL of a challenge of a local source
+        id: String,
+    },
+
+    /// Check step by step that crackmes.one still looks the way the scraper expects
+    Doctor,
+
+    /// Download a challenge by ID, or every challenge matching the filters
+    Download {
+        /// Challenge ID, as found at the end of its crackmes.one URL, or the
+        /// URL of a challenge of a local source
+        #[arg(
+            required_unless_present_any = ["name", "author", "difficulty", "quality", "language", "arch", "platform"],
+            conflicts_with_all = ["name", "author", "difficulty", "quality", "language", "arch", "platform", "pages"]
+        )]
+        id: Option<String>,
+
+        #[command(flatten)]
+        search: SearchArgs,
+
+        #[command(flatten)]
+        download: DownloadArgs,
+
+        /// Number of result pages to download from
+        #[arg(long, default_value_t = 1)]
+        pages: usize,
+    },
+}
+
+#[derive(Args, Debug, Default)]
+pub struct DownloadArgs {
+    /// Directory to download challenges into [default: current directory]
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Extract downloaded archives next to the original zip
+    #[arg(short = 'x', long)]
+    pub extract: bool,
+
+    /// Password of the challenge archives
+    #[arg(long, default_value = DEFAULT_PASSWORD, requires = "extract")]
+    pub password: String,
+
+    /// Only download the zip instead of laying out a challenge workspace
+    #[arg(long)]
+    pub no_workspace: bool,
+
+    /// Directory of workspace templates [default: <config dir>/cracked/templates]
+    #[arg(long, conflicts_with = "no_workspace")]
+    pub templates: Option<PathBuf>,
+}
+
+impl DownloadArgs {
+    pub fn to_download_options(&self, config: &Config) -> DownloadOptions {
+        let output_dir = self
+            .output_dir
+            .clone()
+            .or_else(|| config.output_dir.clone())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let extract = self.extract.then(|| ExtractOptions {
+            password: self.password.clone(),
+            ..ExtractOptions::default()
+        });
+        let workspace = (!self.no_workspace).then(|| WorkspaceOptions {
+            templates_dir: self
+                .templates
+                .clone()
+                .or_else(WorkspaceOptions::default_templates_dir),
+        });
+
+        DownloadOptions {
+            output_dir,
+            extract,
+            workspace,
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct IndexArgs {
+    /// Search the local index of seen challenges instead of crackmes.one
+    #[arg(long, group = "source")]
+    pub offline: bool,
+
+    /// Browse a directory made by `cracked mirror`, without any network access
+    #[arg(long, value_name = "DIR", group = "source")]
+    pub mirror: Option<PathBuf>,
+
+    /// Full-text search over names, authors and descriptions of the index
+    #[arg(long, requires = "source")]
+    pub query: Option<String>,
+}
+
+impl IndexArgs {
+    pub fn is_offline(&self) -> bool {
+        self.offline || self.mirror.is_some()
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct SearchArgs {
     /// Challenge name to search for
     #[arg(short, long)]
     pub name: Option<String>,
@@ -34,6 +250,17 @@ pub struct Cli {
     pub platform: Option<PlatformArg>,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// Tab-separated lines without a header, for reading in a terminal
+    Text,
+    Json,
+    Jsonl,
+    Csv,
+    Tsv,
+    Markdown,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum DifficultyLevel {
     #[value(name = "easy")]
@@ -115,7 +342,7 @@ impl From<LanguageArg> for Language {
             LanguageArg::Borland => Language::Borland,
             LanguageArg::Pascal => Language::Pascal,
             LanguageArg::Dotnet => Language::Dotnet,
-            LanguageArg::Other => Language::Other,
+            LanguageArg::Other => Language::default(),
         }
     }
 }
@@ -147,7 +374,7 @@ impl From<ArchArg> for Arch {
             ArchArg::Arm => Arch::Arm,
             ArchArg::Mips => Arch::Mips,
             ArchArg::Riscv => Arch::RiscV,
-            ArchArg::Other => Arch::Other,
+            ArchArg::Other => Arch::default(),
         }
     }
 }
@@ -188,12 +415,12 @@ impl From<PlatformArg> for Platform {
             PlatformArg::Windows7 => Platform::Windows7,
             PlatformArg::Android => Platform::Android,
             PlatformArg::Ios => Platform::Ios,
-            PlatformArg::Other => Platform::Other,
+            PlatformArg::Other => Platform::default(),
         }
     }
 }
 
-impl Cli {
+impl SearchArgs {
     pub fn to_search_params(&self, token: String) -> SearchParams {
         // If no difficulty or quality is specified, default to full range (1-6)
         let difficulty_range = Some(self.difficulty.map(|d| d.to_range()).unwrap_or([1, 6]));
@@ -207,7 +434,45 @@ impl Cli {
             language: self.language.map(|l| l.into()),
             arch: self.arch.map(|a| a.into()),
             platform: self.platform.map(|p| p.into()),
+            query: None,
             token,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bare_filters_default_to_tui() {
+        let cli = Cli::try_parse_from(["cracked", "-n", "keygen"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.search.name.as_deref(), Some("keygen"));
+    }
+
+    #[test]
+    fn test_query_requires_offline() {
+        assert!(Cli::try_parse_from(["cracked", "search", "--query", "vm"]).is_err());
+        let cli = Cli::try_parse_from(["cracked", "--offline", "--query", "vm"]).unwrap();
+        assert!(cli.index.offline);
+        let cli = Cli::try_parse_from(["cracked", "--mirror", "dump", "--query", "vm"]).unwrap();
+        assert!(cli.index.is_offline());
+        assert!(Cli::try_parse_from(["cracked", "--offline", "--mirror", "dump"]).is_err());
+    }
+
+    #[test]
+    fn test_download_requires_id_or_filters() {
+        assert!(Cli::try_parse_from(["cracked", "download"]).is_err());
+        assert!(Cli::try_parse_from(["cracked", "download", "abc123"]).is_ok());
+        assert!(Cli::try_parse_from(["cracked", "download", "--author", "bob"]).is_ok());
+    }
+
+    #[test]
+    fn test_download_id_rejects_filters() {
+        let args = ["cracked", "download", "abc123", "--author", "bob"];
+        assert!(Cli::try_parse_from(args).is_err());
+        let args = ["cracked", "download", "abc123", "--pages", "2"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+}
@@ -0,0 +1,424 @@
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+use tokio::task::JoinSet;
+
+use crate::{
+    challenge::{Challenge, ChallengeDetails},
+    cli::{DownloadArgs, IndexArgs, OutputFormat, SearchArgs},
+    config::Config,
+    doctor::{self, Outcome},
+    downloads::{DownloadEvent, DownloadManager},
+    errors::CoreError,
+    index::ChallengeIndex,
+    mirror::{Mirror, RateLimiter},
+    output::write_challenges,
+    progress::{self, ProgressStore},
+    recommend::Recommender,
+    roulette,
+    search::{BASE_URL, SearchCursor, SearchPage, SearchParams, get_search_token},
+    source::{ChallengeSource, CrackmesOne, IndexSource, MirrorSource, Sources},
+};
+
+async fn search_challenges(
+    search: &SearchArgs,
+    pages: Option<usize>,
+    config: &Config,
+) -> Result<Vec<Challenge>, CoreError> {
+    let sources = Sources::from_config(config)?;
+    let results = sources
+        .search(search.to_search_params(String::new()), pages)
+        .await?;
+    warn_about(&results);
+    remember(&results.challenges, None);
+    Ok(results.challenges)
+}
+
+/// Tells on stderr what the parser could not read, so that a change of the
+/// site layout does not pass for an empty search.
+fn warn_about(page: &SearchPage) {
+    for source in &page.unavailable {
+        eprintln!("Warning: could not search {}", source);
+    }
+    for issue in &page.issues {
+        eprintln!("Warning: {}", issue);
+    }
+    if page.challenges.is_empty() && !page.issues.is_empty() {
+        eprintln!("Warning: no challenge could be read, crackmes.one may have changed its layout");
+    }
+}
+
+/// Adds what we just fetched to the local index. The index is a cache, so
+/// failing to update it never fails the command.
+fn remember(challenges: &[Challenge], details: Option<&ChallengeDetails>) {
+    let Ok(mut index) = ChallengeIndex::open_default() else {
+        return;
+    };
+    let _ = index.record(challenges);
+    if let (Some(challenge), Some(description)) = (
+        challenges.first(),
+        details.and_then(|details| details.description.as_deref()),
+    ) {
+        let _ = index.set_description(&challenge.url, description);
+    }
+}
+
+/// The sources to browse: the mirror or the local index in place of
+/// crackmes.one with `--mirror` or `--offline`, plus the local directories.
+pub fn open_sources(index: &IndexArgs, config: &Config) -> Result<Sources, CoreError> {
+    let site: Box<dyn ChallengeSource> = match &index.mirror {
+        Some(directory) => Box::new(MirrorSource::open(directory)?),
+        None if index.offline => Box::new(IndexSource::new(ChallengeIndex::open_default()?)),
+        None => Box::new(CrackmesOne::default()),
+    };
+    Sources::with_site(site, config)
+}
+
+// A bare ID is a crackmes.one challenge, anything else the URL of one
+fn challenge_url(id: &str) -> String {
+    if id.contains('/') {
+        id.to_string()
+    } else {
+        format!("/crackme/{}", id)
+    }
+}
+
+pub async fn search(
+    search: &SearchArgs,
+    index: &IndexArgs,
+    pages: Option<usize>,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<(), CoreError> {
+    let challenges = if index.is_offline() {
+        let params = SearchParams {
+            query: index.query.clone(),
+            ..search.to_search_params(String::new())
+        };
+        let results = open_sources(index, config)?.search(params, pages).await?;
+        warn_about(&results);
+        results.challenges
+    } else {
+        search_challenges(search, pages, config).await?
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    match write_challenges(&mut stdout, &challenges, format) {
+        // Nothing is left to report to behind a closed pipe
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+        result => result?,
+    }
+    eprintln!("{} challenges found", challenges.len());
+    Ok(())
+}
+
+pub async fn recommend(
+    search: &SearchArgs,
+    pages: usize,
+    count: usize,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<(), CoreError> {
+    let progress = ProgressStore::load()?;
+    let recommender = Recommender::new(&progress);
+
+    let mut params = search.to_search_params(String::new());
+    if search.difficulty.is_none() {
+        // Only fetch challenges that stand a chance of being recommended
+        params.difficulty_range = Some(recommender.difficulty_range());
+    }
+    let results = Sources::from_config(config)?
+        .search(params, Some(pages))
+        .await?;
+    warn_about(&results);
+    remember(&results.challenges, None);
+    let challenges = results.challenges;
+    let recommended: Vec<Challenge> = recommender
+        .rank(&challenges, &progress, count)
+        .into_iter()
+        .map(|recommendation| challenges[recommendation.index].clone())
+        .collect();
+
+    let mut stdout = std::io::stdout().lock();
+    match write_challenges(&mut stdout, &recommended, format) {
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+        result => result?,
+    }
+    eprintln!(
+        "Comfort level {:.1}, recommending challenges around difficulty {:.1}",
+        recommender.comfort_level, recommender.target
+    );
+    Ok(())
+}
+
+pub async fn random(
+    search: &SearchArgs,
+    pages: usize,
+    weighted: bool,
+    format: OutputFormat,
+    config: &Config,
+) -> Result<(), CoreError> {
+    let progress = ProgressStore::load()?;
+    let challenges = search_challenges(search, Some(pages), config).await?;
+    let candidates: Vec<&Challenge> = challenges.iter().collect();
+
+    let mut rng = fastrand::Rng::new();
+    let Some(index) = roulette::pick(&candidates, &progress, weighted, &mut rng) else {
+        eprintln!(
+            "All {} matching challenges are solved or downloaded",
+            challenges.len()
+        );
+        return Ok(());
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    match write_challenges(&mut stdout, &challenges[index..=index], format) {
+        // Nobody is left to read the pick behind a closed pipe
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+pub async fn info(id: &str, config: &Config) -> Result<(), CoreError> {
+    let sources = Sources::from_config(config)?;
+    let url = challenge_url(id);
+    let source = sources.for_url(&url);
+    let challenge = source.challenge(&url).await?;
+    let details = source.details(&challenge.url).await?;
+    if source.is_remote() {
+        remember(std::slice::from_ref(&challenge), Some(&details));
+    }
+    print_info(&challenge, &details);
+    Ok(())
+}
+
+/// Runs the scraper health check against crackmes.one and prints, for each
+/// broken step, the assumption that no longer holds and what the site sent.
+pub async fn doctor() -> Result<(), CoreError> {
+    let checks = doctor::run(BASE_URL).await;
+    for check in &checks {
+        match &check.outcome {
+            Outcome::Passed(detail) => println!("ok    {:<17} {}", check.step, detail),
+            Outcome::Failed {
+                assumption,
+                snippet,
+            } => {
+                println!("FAIL  {:<17} expected {}", check.step, assumption);
+                for line in snippet.lines() {
+                    println!("      | {}", line);
+                }
+            }
+            Outcome::Skipped => println!("skip  {}", check.step),
+        }
+    }
+
+    let failed = checks.iter().filter(|check| check.failed()).count();
+    if failed > 0 {
+        return Err(CoreError::HealthCheckFailure { failed });
+    }
+    Ok(())
+}
+
+/// Walks the latest uploads until a page brings nothing new, or through every
+/// page with `full`.
+pub async fn sync(full: bool) -> Result<(), CoreError> {
+    let mut index = ChallengeIndex::open_default()?;
+    let (cookie_token, html_token) = get_search_token(BASE_URL).await?;
+    let params = SearchArgs::default().to_search_params(html_token);
+    let mut cursor = SearchCursor::new(BASE_URL, params, cookie_token);
+
+    let mut added = 0;
+    while let Some(page) = cursor.next_page().await? {
+        warn_about(&page);
+        let new = index.record(&page.challenges)?;
+        added += new;
+        eprintln!("Page {}: {} new challenges", page.page, new);
+        if new == 0 && !full {
+            break;
+        }
+    }
+    eprintln!("{} new challenges, {} in the index", added, index.len());
+    Ok(())
+}
+
+/// Lists the catalogue, then fetches the page, writeups and archive of every
+/// challenge not mirrored yet. Stopping and running it again resumes the crawl.
+pub async fn mirror(directory: &Path, concurrency: usize, delay: u64) -> Result<(), CoreError> {
+    let mirror = Arc::new(Mirror::open(directory)?);
+    let mut index = mirror.index()?;
+    let limiter = Arc::new(RateLimiter::new(Duration::from_millis(delay)));
+    let mut state = mirror.load_state()?;
+
+    // Renews the session tokens when they expire during a long crawl
+    let site = CrackmesOne::default();
+    let params = SearchArgs::default().to_search_params(String::new());
+    // Once the catalogue was listed, only look for the latest uploads
+    let mut page = if state.listing_complete {
+        1
+    } else {
+        state.next_page
+    };
+    loop {
+        limiter.wait().await;
+        let results = site.search(&params, page).await?;
+        warn_about(&results);
+        let new = mirror.record(&mut index, &results.challenges)?;
+        eprintln!("Listed page {}: {} new challenges", page, new);
+
+        let last_page = !results.has_next || results.challenges.is_empty();
+        if state.listing_complete {
+            if new == 0 || last_page {
+                break;
+            }
+        } else if last_page {
+            state.listing_complete = true;
+            mirror.save_state(&state)?;
+            break;
+        } else {
+            state.next_page = page + 1;
+            mirror.save_state(&state)?;
+        }
+        page += 1;
+    }
+
+    let mut pending = mirror.pending()?.into_iter();
+    eprintln!("{} challenges left to mirror", pending.len());
+    let mut tasks = JoinSet::new();
+    let mut failures = 0;
+    loop {
+        while tasks.len() < concurrency.max(1) {
+            let Some(challenge) = pending.next() else {
+                break;
+            };
+            let (mirror, limiter) = (mirror.clone(), limiter.clone());
+            tasks.spawn(async move {
+                let result = mirror.fetch(&challenge, &limiter).await;
+                (challenge, result)
+            });
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        match joined {
+            Ok((challenge, Ok(details))) => {
+                if let Some(description) = &details.description {
+                    let _ = index.set_description(&challenge.url, description);
+                }
+                eprintln!("Mirrored {}", challenge.name);
+            }
+            Ok((challenge, Err(error))) => {
+                eprintln!("Failed to mirror {}: {}", challenge.name, error);
+                failures += 1;
+            }
+            Err(_) => failures += 1,
+        }
+    }
+
+    eprintln!("{} challenges in the mirror", index.len());
+    if failures > 0 {
+        eprintln!(
+            "{} challenges failed, run the mirror again to retry them",
+            failures
+        );
+        return Err(CoreError::MirrorFailure { failed: failures });
+    }
+    Ok(())
+}
+
+pub async fn download(
+    id: Option<&str>,
+    search: &SearchArgs,
+    download: &DownloadArgs,
+    pages: usize,
+    config: &Config,
+) -> Result<(), CoreError> {
+    let sources = Arc::new(Sources::from_config(config)?);
+    let challenges = match id {
+        Some(id) => {
+            let url = challenge_url(id);
+            vec![sources.for_url(&url).challenge(&url).await?]
+        }
+        None => search_challenges(search, Some(pages), config).await?,
+    };
+    let mut progress = ProgressStore::load()?;
+    let mut downloads = DownloadManager::new(download.to_download_options(config), sources);
+    let mut queued = HashMap::new();
+    for challenge in challenges {
+        queued.insert(downloads.enqueue(challenge.clone()), challenge);
+    }
+
+    let mut failures = 0;
+    while downloads.pending() > 0 {
+        let Some(event) = downloads.next_event().await else {
+            break;
+        };
+        match event {
+            DownloadEvent::Finished { id, outcome } => {
+                progress.mark_downloaded(&queued[&id], progress::now());
+                progress.save()?;
+                // Same layout as sha256sum, so the output can be checked with it
+                println!("{}  {}", outcome.sha256, outcome.archive.display());
+                if let Some(workspace) = &outcome.workspace {
+                    println!("{}", workspace.directory.display());
+                }
+                if let Some(report) = &outcome.extracted {
+                    for file in &report.files {
+                        println!("{}", report.directory.join(file).display());
+                    }
+                }
+            }
+            DownloadEvent::Started { id, attempt } if attempt > 1 => {
+                eprintln!("Retrying {} (attempt {})", queued[&id].name, attempt);
+            }
+            DownloadEvent::Failed { id, error } => {
+                eprintln!("Failed to download {}: {}", queued[&id].name, error);
+                failures += 1;
+            }
+            _ => {}
+        }
+    }
+
+    if failures > 0 {
+        return Err(CoreError::DownloadFailure { failed: failures });
+    }
+    Ok(())
+}
+
+fn print_info(challenge: &Challenge, details: &ChallengeDetails) {
+    let unknown = || String::from("-");
+
+    println!("Name:        {}", challenge.name);
+    println!("ID:          {}", challenge.id());
+    println!("Author:      {}", challenge.author);
+    println!("Language:    {}", challenge.language);
+    println!("Arch:        {}", challenge.arch);
+    println!("Platform:    {}", challenge.platform);
+    println!("Difficulty:  {:.1}", challenge.difficulty);
+    println!("Quality:     {:.1}", challenge.quality);
+    println!(
+        "Uploaded:    {}",
+        details.upload_date.clone().unwrap_or_else(unknown)
+    );
+    println!(
+        "Downloads:   {}",
+        details
+            .downloads
+            .map(|d| d.to_string())
+            .unwrap_or_else(unknown)
+    );
+    println!(
+        "Writeups:    {}",
+        details
+            .writeups
+            .map(|w| w.to_string())
+            .unwrap_or_else(unknown)
+    );
+    println!(
+        "Hash:        {}",
+        details.hash.clone().unwrap_or_else(unknown)
+    );
+
+    if let Some(description) = &details.description {
+        println!();
+        println!("{}", description);
+    }
+}
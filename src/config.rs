@@ -0,0 +1,54 @@
+use std::{fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{errors::CoreError, source::LocalSourceConfig};
+
+/// Settings read from `<config dir>/cracked/config.toml`. Command line flags
+/// take precedence over them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where challenges are downloaded, the current directory by default.
+    pub output_dir: Option<PathBuf>,
+    /// Directory of the learning paths, `<config dir>/cracked/paths` by default.
+    pub paths_dir: Option<PathBuf>,
+    /// Local directories of challenges, listed alongside crackmes.one.
+    pub sources: Vec<LocalSourceConfig>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cracked").join("config.toml"))
+    }
+
+    /// Loads the configuration file, if there is one.
+    pub fn load() -> Result<Self, CoreError> {
+        match Self::path().and_then(|path| fs::read_to_string(path).ok()) {
+            Some(content) => Self::parse(&content),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, CoreError> {
+        toml::from_str(content).map_err(CoreError::InvalidConfig)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(r#"output_dir = "/tmp/crackmes""#).unwrap();
+        assert_eq!(config.output_dir, Some(PathBuf::from("/tmp/crackmes")));
+
+        assert!(Config::parse("").unwrap().output_dir.is_none());
+        assert!(Config::parse("unknown = 1").is_err());
+
+        let config =
+            Config::parse("[[sources]]\nname = \"team\"\npath = \"/srv/crackmes\"").unwrap();
+        assert_eq!(config.sources[0].name, "team");
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    challenge::Challenge,
+    errors::CoreError,
+    progress::{ProgressStatus, ProgressStore},
+    search::{Arch, Language, Platform, SearchParams},
+};
+
+/// A training path, read from a TOML file of `<config dir>/cracked/paths`.
+///
+/// ```toml
+/// name = "Onboarding"
+///
+/// [[stages]]
+/// name = "First steps"
+/// challenges = ["/crackme/6612c5a3a9b3e5f9e1d0c0a1"]
+///
+/// [[stages]]
+/// name = "x86 keygens"
+/// required = 3
+/// filter = { arch = "x86", difficulty = [2, 3] }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LearningPath {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub stages: Vec<Stage>,
+}
+
+/// Challenges to solve before moving on, listed by URL or picked by a filter.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stage {
+    pub name: String,
+    #[serde(default)]
+    pub challenges: Vec<String>,
+    #[serde(default)]
+    pub filter: Option<StageFilter>,
+    /// Solves needed to complete the stage, every listed challenge by default.
+    #[serde(default)]
+    pub required: Option<usize>,
+}
+
+/// Same fields as the search form, with labels as crackmes.one spells them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StageFilter {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub difficulty: Option<[i32; 2]>,
+    pub quality: Option<[i32; 2]>,
+    pub language: Option<Language>,
+    pub arch: Option<Arch>,
+    pub platform: Option<Platform>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageProgress {
+    pub solved: usize,
+    pub required: usize,
+    /// Every previous stage is complete.
+    pub unlocked: bool,
+}
+
+impl StageProgress {
+    pub fn is_complete(&self) -> bool {
+        self.solved >= self.required
+    }
+}
+
+impl StageFilter {
+    pub fn to_search_params(&self, token: String) -> SearchParams {
+        SearchParams {
+            name: self.name.clone(),
+            author: self.author.clone(),
+            difficulty_range: Some(self.difficulty.unwrap_or([1, 6])),
+            quality_range: Some(self.quality.unwrap_or([1, 6])),
+            language: self.language.clone(),
+            arch: self.arch.clone(),
+            platform: self.platform.clone(),
+            query: None,
+            token,
+        }
+    }
+
+    /// Mirrors the site search, so that solves count towards the stage
+    /// without fetching anything.
+    pub fn matches(&self, challenge: &Challenge) -> bool {
+        // Unlike a search, missing ranges leave unrated challenges in
+        SearchParams {
+            difficulty_range: self.difficulty,
+            quality_range: self.quality,
+            ..self.to_search_params(String::new())
+        }
+        .matches(challenge)
+    }
+}
+
+impl Stage {
+    /// URLs of the listed challenges, as found in `Challenge::url`.
+    pub fn challenge_urls(&self) -> Vec<String> {
+        self.challenges
+            .iter()
+            .map(|url| relative_url(url).to_string())
+            .collect()
+    }
+
+    fn solved(&self, store: &ProgressStore) -> usize {
+        let urls = self.challenge_urls();
+        store
+            .entries()
+            .filter(|entry| entry.status == ProgressStatus::Solved)
+            .filter(|entry| {
+                urls.contains(&entry.challenge.url)
+                    || self
+                        .filter
+                        .as_ref()
+                        .is_some_and(|filter| filter.matches(&entry.challenge))
+            })
+            .count()
+    }
+
+    fn required(&self) -> usize {
+        self.required.unwrap_or(self.challenges.len()).max(1)
+    }
+}
+
+impl LearningPath {
+    /// Progress of every stage. A stage unlocks once all the previous ones are
+    /// complete.
+    pub fn progress(&self, store: &ProgressStore) -> Vec<StageProgress> {
+        let mut unlocked = true;
+        self.stages
+            .iter()
+            .map(|stage| {
+                let progress = StageProgress {
+                    solved: stage.solved(store),
+                    required: stage.required(),
+                    unlocked,
+                };
+                unlocked = unlocked && progress.is_complete();
+                progress
+            })
+            .collect()
+    }
+
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CoreError> {
+        Self::parse(&fs::read_to_string(path)?).map_err(|source| CoreError::InvalidLearningPath {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+pub fn default_paths_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("cracked").join("paths"))
+}
+
+/// Loads every `.toml` file of `directory`, sorted by file name, along with
+/// the errors of the files that could not be read, which are skipped. A
+/// missing directory simply has no paths.
+pub fn load_paths(directory: &Path) -> Result<(Vec<LearningPath>, Vec<CoreError>), CoreError> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .collect(),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(error) => return Err(error.into()),
+    };
+    files.sort();
+
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    for file in &files {
+        match LearningPath::load(file) {
+            Ok(path) => paths.push(path),
+            Err(error) => errors.push(error),
+        }
+    }
+    Ok((paths, errors))
+}
+
+fn relative_url(url: &str) -> &str {
+    let url = url.trim();
+    url.strip_prefix("https://crackmes.one")
+        .or_else(|| url.strip_prefix("http://crackmes.one"))
+        .unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = r#"
+        name = "Onboarding"
+
+        [[stages]]
+        name = "First steps"
+        challenges = ["https://crackmes.one/crackme/first", "/crackme/second"]
+        required = 1
+
+        [[stages]]
+        name = "x86"
+        required = 2
+        filter = { arch = "x86", difficulty = [1, 2] }
+
+        [[stages]]
+        name = "Graduation"
+        challenges = ["/crackme/final"]
+    "#;
+
+    fn challenge(id: &str, difficulty: f32, arch: Arch) -> Challenge {
+        Challenge {
+            arch,
+            difficulty,
+            ..Challenge::sample(id)
+        }
+    }
+
+    #[test]
+    fn test_stages_unlock_in_order() {
+        let path = LearningPath::parse(PATH).unwrap();
+        let mut store = ProgressStore::default();
+        store.set_status(
+            &challenge("second", 1.0, Arch::X86),
+            ProgressStatus::Solved,
+            1,
+        );
+        store.set_status(
+            &challenge("other", 1.5, Arch::X86),
+            ProgressStatus::Solved,
+            2,
+        );
+        store.set_status(&challenge("arm", 1.5, Arch::Arm), ProgressStatus::Solved, 3);
+
+        let progress = path.progress(&store);
+        assert_eq!(
+            progress,
+            vec![
+                StageProgress {
+                    solved: 1,
+                    required: 1,
+                    unlocked: true
+                },
+                StageProgress {
+                    solved: 2,
+                    required: 2,
+                    unlocked: true
+                },
+                StageProgress {
+                    solved: 0,
+                    required: 1,
+                    unlocked: true
+                },
+            ]
+        );
+
+        let store = ProgressStore::default();
+        let progress = path.progress(&store);
+        assert!(progress[0].unlocked && !progress[1].unlocked && !progress[2].unlocked);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_fields() {
+        assert!(LearningPath::parse("name = \"x\"\nstages = []\nlevel = 3").is_err());
+    }
+
+    #[test]
+    fn test_load_paths_reads_toml_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.toml"), PATH).unwrap();
+        fs::write(dir.path().join("a.toml"), "name = \"Empty\"\nstages = []").unwrap();
+        fs::write(dir.path().join("notes.md"), "not a path").unwrap();
+        fs::write(dir.path().join("broken.toml"), "name = ").unwrap();
+
+        let (paths, errors) = load_paths(dir.path()).unwrap();
+        let names: Vec<&str> = paths.iter().map(|path| path.name.as_str()).collect();
+        assert_eq!(names, vec!["Empty", "Onboarding"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("broken.toml"));
+
+        let (paths, errors) = load_paths(&dir.path().join("missing")).unwrap();
+        assert!(paths.is_empty() && errors.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+use reqwest::{Client, Response};
+use scraper::{Html, Selector};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    challenge::Challenge,
+    cli::SearchArgs,
+    search::{
+        parse_challenge_details, parse_challenge_page, parse_search_page, read_cookie_token,
+        read_html_token, search_request, search_token_request, url_on,
+    },
+};
+
+/// The steps of the health check, in the order the scraper goes through them.
+const STEPS: [&str; 7] = [
+    "token page",
+    "session cookie",
+    "form token",
+    "search request",
+    "result rows",
+    "challenge page",
+    "archive download",
+];
+
+// Long enough to recognise the markup, short enough to fit in a terminal
+const SNIPPET_LENGTH: usize = 600;
+
+/// How one step of the health check went.
+#[derive(Debug)]
+pub enum Outcome {
+    Passed(String),
+    /// The assumption the scraper relies on, and what the site sent instead.
+    Failed {
+        assumption: String,
+        snippet: String,
+    },
+    /// An earlier step failed, so there was nothing to check.
+    Skipped,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub step: &'static str,
+    pub outcome: Outcome,
+}
+
+impl Check {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed { .. })
+    }
+}
+
+/// Goes through every request the scraper makes against `base_url`, checking
+/// each assumption it makes about the answers. Steps after the first broken
+/// one are skipped.
+pub async fn run(base_url: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let _ = walk(base_url, &mut checks).await;
+    for step in &STEPS[checks.len()..] {
+        checks.push(Check {
+            step,
+            outcome: Outcome::Skipped,
+        });
+    }
+    checks
+}
+
+// Returns `None` as soon as a step fails
+async fn walk(base_url: &str, checks: &mut Vec<Check>) -> Option<()> {
+    let client = Client::new();
+    let search_url = format!("{}/search", base_url);
+
+    let response = get(
+        checks,
+        search_token_request(&client, base_url).send().await,
+        "GET",
+        &search_url,
+    )
+    .await?;
+    let headers = response.headers().clone();
+    let body = response.text().await.unwrap_or_default();
+    pass(checks, format!("{} bytes", body.len()));
+
+    let set_cookie = headers
+        .get("set-cookie")
+        .and_then(|header| header.to_str().ok());
+    let Some(cookie_token) = set_cookie.and_then(read_cookie_token) else {
+        let snippet = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value.to_str().unwrap_or("<binary>")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        return fail(
+            checks,
+            "the search page sets a session cookie, `Set-Cookie: gosess=<token>; ...`",
+            snippet,
+        );
+    };
+    let cookie_token = cookie_token.to_string();
+    pass(checks, format!("gosess={}", cookie_token));
+
+    let Some(html_token) = read_html_token(&body) else {
+        let snippet = around(&body, "token")
+            .unwrap_or_else(|| around(&body, "<form").unwrap_or_else(|| head(&body)));
+        return fail(
+            checks,
+            r#"the search form has `<input id="token" value="...">` on a single line"#,
+            snippet,
+        );
+    };
+    pass(checks, html_token.to_string());
+
+    let params = SearchArgs::default().to_search_params(html_token.to_string());
+    let response = search_request(&client, base_url, &params, &cookie_token, 1)
+        .send()
+        .await;
+    let response = get(checks, response, "POST", &search_url).await?;
+    let body = response.text().await.unwrap_or_default();
+    pass(checks, format!("{} bytes", body.len()));
+
+    let page = parse_search_page(&body, 1);
+    let Some(challenge) = page.challenges.first().cloned() else {
+        let assumption = match page.issues.first() {
+            Some(issue) => format!("every result row can be read, but {}", issue),
+            None => String::from(
+                r#"an unfiltered search lists challenges in `<tbody id="content-list">` rows"#,
+            ),
+        };
+        return fail(checks, &assumption, results_snippet(&body));
+    };
+    match page.issues.first() {
+        // Some rows made it, so the following steps can still be checked
+        Some(issue) => {
+            let _: Option<()> = fail(
+                checks,
+                &format!("every result row can be read, but {}", issue),
+                results_snippet(&body),
+            );
+        }
+        None => pass(
+            checks,
+            format!(
+                "{} challenges, first is {}",
+                page.challenges.len(),
+                challenge.name
+            ),
+        ),
+    }
+
+    let challenge_url = url_on(base_url, &challenge.url);
+    let response = get(
+        checks,
+        client.get(&challenge_url).send().await,
+        "GET",
+        &challenge_url,
+    )
+    .await?;
+    let body = response.text().await.unwrap_or_default();
+    if parse_challenge_page(&body, challenge.url.clone()).is_none() {
+        let snippet = around(&body, "Author").unwrap_or_else(|| head(&body));
+        return fail(
+            checks,
+            "the challenge page labels its fields, as in `<li>Author: ...</li>` and `<li>Difficulty: 2.5</li>`",
+            snippet,
+        );
+    }
+    let details = parse_challenge_details(&body);
+    let expected = details.sha256().map(String::from);
+    pass(
+        checks,
+        format!(
+            "{} writeups, {}",
+            details.writeup_links.len(),
+            if expected.is_some() {
+                "SHA-256 published"
+            } else {
+                "no SHA-256, downloads will not be verified"
+            }
+        ),
+    );
+
+    check_archive(&client, base_url, &challenge, expected.as_deref(), checks).await
+}
+
+async fn check_archive(
+    client: &Client,
+    base_url: &str,
+    challenge: &Challenge,
+    expected: Option<&str>,
+    checks: &mut Vec<Check>,
+) -> Option<()> {
+    let url = challenge.get_download_url(base_url);
+    let response = get(checks, client.get(&url).send().await, "GET", &url).await?;
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or("none")
+        .to_string();
+    let bytes = response.bytes().await.unwrap_or_default();
+
+    if !bytes.starts_with(b"PK\x03\x04") {
+        let start = String::from_utf8_lossy(&bytes[..bytes.len().min(SNIPPET_LENGTH)]);
+        return fail(
+            checks,
+            &format!("{} serves a zip archive", url),
+            format!("Content-Type: {}\n{}", content_type, start),
+        );
+    }
+    let sha256 = format!("{:x}", Sha256::digest(&bytes));
+    if let Some(expected) = expected
+        && !expected.eq_ignore_ascii_case(&sha256)
+    {
+        return fail(
+            checks,
+            "the archive matches the SHA-256 of the challenge page",
+            format!("expected {}\ngot      {}", expected, sha256),
+        );
+    }
+    pass(checks, format!("{} bytes, sha256 {}", bytes.len(), sha256));
+    Some(())
+}
+
+// Checks that a request went through and got a successful status
+async fn get(
+    checks: &mut Vec<Check>,
+    response: reqwest::Result<Response>,
+    method: &str,
+    url: &str,
+) -> Option<Response> {
+    match response {
+        Ok(response) if response.status().is_success() => Some(response),
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            fail(
+                checks,
+                &format!("{} {} answers with a success status", method, url),
+                format!("HTTP {}\n{}", status, head(&body)),
+            )
+        }
+        Err(error) => fail(
+            checks,
+            &format!("{} {} can be reached", method, url),
+            error.to_string(),
+        ),
+    }
+}
+
+fn pass(checks: &mut Vec<Check>, detail: String) {
+    checks.push(Check {
+        step: STEPS[checks.len()],
+        outcome: Outcome::Passed(detail),
+    });
+}
+
+fn fail<T>(checks: &mut Vec<Check>, assumption: &str, snippet: String) -> Option<T> {
+    checks.push(Check {
+        step: STEPS[checks.len()],
+        outcome: Outcome::Failed {
+            assumption: assumption.to_string(),
+            snippet,
+        },
+    });
+    None
+}
+
+// The results table header and first row, or the start of the page if there
+// is no table at all
+fn results_snippet(body: &str) -> String {
+    let document = Html::parse_document(body);
+    let selector = Selector::parse("thead, tbody#content-list tr, table").unwrap();
+    let parts: Vec<String> = document
+        .select(&selector)
+        .take(2)
+        .map(|element| element.html())
+        .collect();
+    if parts.is_empty() {
+        head(body)
+    } else {
+        truncate(&parts.join("\n"))
+    }
+}
+
+fn around(text: &str, needle: &str) -> Option<String> {
+    let position = text.find(needle)?;
+    let start = text[..position]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_LENGTH / 4)
+        .map(|(index, _)| index)
+        .unwrap_or(0);
+    Some(truncate(&text[start..]))
+}
+
+fn head(text: &str) -> String {
+    truncate(text)
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{MockServer, Route};
+
+    #[tokio::test]
+    async fn test_every_step_passes_on_the_recorded_site() {
+        let server = MockServer::crackmes().await;
+        let checks = run(&server.url()).await;
+
+        let steps: Vec<&str> = checks.iter().map(|check| check.step).collect();
+        assert_eq!(steps, STEPS);
+        for check in &checks {
+            assert!(
+                matches!(check.outcome, Outcome::Passed(_)),
+                "{}: {:?}",
+                check.step,
+                check.outcome
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_the_broken_assumption_with_a_snippet() {
+        let token_page = r#"<form><input type="hidden" id="csrf" value="f0rm-t0ken"></form>"#;
+        let server = MockServer::start(vec![
+            Route::new("GET", "/search", token_page).header("Set-Cookie", "gosess=c00k1e"),
+        ])
+        .await;
+        let checks = run(&server.url()).await;
+
+        assert!(matches!(checks[1].outcome, Outcome::Passed(_)));
+        let Outcome::Failed {
+            assumption,
+            snippet,
+        } = &checks[2].outcome
+        else {
+            panic!("expected the form token to fail: {:?}", checks[2].outcome);
+        };
+        assert!(assumption.contains(r#"id="token""#));
+        assert!(snippet.contains(r#"id="csrf""#));
+        assert!(
+            checks[3..]
+                .iter()
+                .all(|check| matches!(check.outcome, Outcome::Skipped))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reports_a_changed_results_table() {
+        let table = r#"<table><thead><tr><th>Title</th><th>Author</th></tr></thead>
+            <tbody id="content-list"><tr><td>easy_keygen</td><td>bob</td></tr></tbody></table>"#;
+        let server = MockServer::start(vec![
+            Route::new("GET", "/search", r#"<input id="token" value="t">"#)
+                .header("Set-Cookie", "gosess=c"),
+            Route::new("POST", "/search", table),
+        ])
+        .await;
+        let checks = run(&server.url()).await;
+
+        assert!(matches!(checks[3].outcome, Outcome::Passed(_)));
+        let Outcome::Failed {
+            assumption,
+            snippet,
+        } = &checks[4].outcome
+        else {
+            panic!("expected the result rows to fail: {:?}", checks[4].outcome);
+        };
+        assert!(assumption.contains("\"Title\""));
+        assert!(snippet.contains("<th>Title</th>"));
+        assert!(matches!(checks[5].outcome, Outcome::Skipped));
+    }
+}
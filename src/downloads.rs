@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::{Semaphore, mpsc};
+
+use crate::{
+    challenge::{Challenge, DownloadOptions, DownloadOutcome},
+    errors::CoreError,
+    source::Sources,
+};
+
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Progress reports sent by the download tasks.
+pub enum DownloadEvent {
+    Started {
+        id: usize,
+        attempt: u32,
+    },
+    Progress {
+        id: usize,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Finished {
+        id: usize,
+        outcome: DownloadOutcome,
+    },
+    Failed {
+        id: usize,
+        error: CoreError,
+    },
+    Cancelled {
+        id: usize,
+    },
+}
+
+/// Runs challenge downloads on tokio tasks, a few at a time, retrying the
+/// ones that fail because of the network.
+pub struct DownloadManager {
+    options: Arc<DownloadOptions>,
+    sources: Arc<Sources>,
+    slots: Arc<Semaphore>,
+    cancel_flags: HashMap<usize, Arc<AtomicBool>>,
+    sender: mpsc::UnboundedSender<DownloadEvent>,
+    receiver: mpsc::UnboundedReceiver<DownloadEvent>,
+    next_id: usize,
+}
+
+impl DownloadManager {
+    pub fn new(options: DownloadOptions, sources: Arc<Sources>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            options: Arc::new(options),
+            sources,
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            cancel_flags: HashMap::new(),
+            sender,
+            receiver,
+            next_id: 0,
+        }
+    }
+
+    pub fn options(&self) -> &DownloadOptions {
+        &self.options
+    }
+
+    /// Queues a download and returns its ID.
+    pub fn enqueue(&mut self, challenge: Challenge) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.cancel_flags.insert(id, cancelled.clone());
+
+        let options = self.options.clone();
+        let sources = self.sources.clone();
+        let slots = self.slots.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let event = match slots.acquire().await {
+                Ok(_permit) => {
+                    run_download(id, &challenge, &options, &sources, &sender, &cancelled).await
+                }
+                Err(_) => DownloadEvent::Cancelled { id },
+            };
+            let _ = sender.send(event);
+        });
+
+        id
+    }
+
+    /// Asks a queued or running download to stop.
+    pub fn cancel(&mut self, id: usize) {
+        if let Some(cancelled) = self.cancel_flags.get(&id) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Events received since the last call, without waiting.
+    pub fn poll_events(&mut self) -> Vec<DownloadEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            self.forget_if_done(&event);
+            events.push(event);
+        }
+        events
+    }
+
+    /// Waits for the next event.
+    pub async fn next_event(&mut self) -> Option<DownloadEvent> {
+        let event = self.receiver.recv().await?;
+        self.forget_if_done(&event);
+        Some(event)
+    }
+
+    /// Number of downloads still queued or running.
+    pub fn pending(&self) -> usize {
+        self.cancel_flags.len()
+    }
+
+    fn forget_if_done(&mut self, event: &DownloadEvent) {
+        match event {
+            DownloadEvent::Finished { id, .. }
+            | DownloadEvent::Failed { id, .. }
+            | DownloadEvent::Cancelled { id } => {
+                self.cancel_flags.remove(id);
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn run_download(
+    id: usize,
+    challenge: &Challenge,
+    options: &DownloadOptions,
+    sources: &Sources,
+    sender: &mpsc::UnboundedSender<DownloadEvent>,
+    cancelled: &AtomicBool,
+) -> DownloadEvent {
+    let progress = |downloaded, total| {
+        let _ = sender.send(DownloadEvent::Progress {
+            id,
+            downloaded,
+            total,
+        });
+    };
+
+    // Best effort, the page does not always publish a hash
+    let source = sources.for_url(&challenge.url);
+    let details = source.details(&challenge.url).await.ok();
+    let expected_sha256 = details
+        .as_ref()
+        .and_then(|details| details.sha256())
+        .map(str::to_string);
+
+    let mut attempt = 1;
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return DownloadEvent::Cancelled { id };
+        }
+        let _ = sender.send(DownloadEvent::Started { id, attempt });
+
+        match challenge
+            .download_with(
+                source,
+                options,
+                expected_sha256.as_deref(),
+                &progress,
+                cancelled,
+            )
+            .await
+        {
+            Ok(outcome) => return DownloadEvent::Finished { id, outcome },
+            Err(CoreError::Cancelled) => return DownloadEvent::Cancelled { id },
+            // Only network failures and corrupted transfers are worth another try
+            Err(error) if error.is_transient() && attempt < MAX_ATTEMPTS => {
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+                attempt += 1;
+            }
+            Err(error) => return DownloadEvent::Failed { id, error },
+        }
+    }
+}
@@ -1,6 +1,312 @@
+use std::{error::Error, path::PathBuf};
+
+use reqwest::StatusCode;
+
+/// What a request to a challenge site was after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    SearchPage,
+    ChallengeList,
+    ChallengePage,
+    Writeup,
+    Archive,
+}
+
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Resource::SearchPage => "search page",
+            Resource::ChallengeList => "challenge list",
+            Resource::ChallengePage => "challenge page",
+            Resource::Writeup => "writeup",
+            Resource::Archive => "archive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug)]
 pub enum CoreError {
-    GetToken,
-    GetChallengeList,
-    DownloadFailure,
+    Io(std::io::Error),
+    /// A request could not be sent, or its answer could not be read.
+    Request {
+        resource: Resource,
+        url: String,
+        source: reqwest::Error,
+    },
+    /// The site answered with an error status.
+    Status {
+        resource: Resource,
+        url: String,
+        status: StatusCode,
+    },
+    /// An answer lacks what the scraper looks for. `location` names the
+    /// header, selector or field it expected.
+    Parse {
+        resource: Resource,
+        url: String,
+        location: String,
+    },
+    /// No challenge source knows this URL.
+    UnknownChallenge(String),
+    /// Some downloads of a batch failed, each was reported on its own.
+    DownloadFailure {
+        failed: usize,
+    },
+    Cancelled,
+    HashMismatch {
+        expected: String,
+        actual: String,
+    },
+    ExtractFailure {
+        archive: PathBuf,
+        source: zip::result::ZipError,
+    },
+    /// An archive escaping its destination or looking like a zip bomb.
+    UnsafeArchive(String),
+    WorkspaceFailure {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A path leaving the directory it has to stay in.
+    UnsafePath(PathBuf),
+    InvalidConfig(toml::de::Error),
+    InvalidProgress(serde_json::Error),
+    InvalidLearningPath {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    IndexFailure(rusqlite::Error),
+    /// There is no data directory to keep the index in.
+    NoDataDirectory,
+    InvalidMirror {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The archive of a challenge is missing from the mirror being browsed.
+    NotMirrored(String),
+    /// Some challenges could not be mirrored, each was reported on its own.
+    MirrorFailure {
+        failed: usize,
+    },
+    InvalidSource {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    HealthCheckFailure {
+        failed: usize,
+    },
+}
+
+impl CoreError {
+    /// For `map_err` on a request that failed or an answer that could not be read.
+    pub fn request(resource: Resource, url: &str) -> impl FnOnce(reqwest::Error) -> Self + '_ {
+        move |source| CoreError::Request {
+            resource,
+            url: url.to_string(),
+            source,
+        }
+    }
+
+    pub fn parse(resource: Resource, url: &str, location: &str) -> Self {
+        CoreError::Parse {
+            resource,
+            url: url.to_string(),
+            location: location.to_string(),
+        }
+    }
+
+    /// Whether trying the same request again may work.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            CoreError::Request { .. } | CoreError::HashMismatch { .. } => true,
+            CoreError::Status { status, .. } => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::RANGE_NOT_SATISFIABLE
+            }
+            _ => false,
+        }
+    }
+
+    /// The context of the error as labelled lines, followed by the chain of
+    /// underlying errors, for the error log of the terminal UI.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = Vec::new();
+        match self {
+            CoreError::Request { url, .. } => details.push(("URL", url.clone())),
+            CoreError::Status { url, status, .. } => {
+                details.push(("URL", url.clone()));
+                details.push(("Status", status.to_string()));
+            }
+            CoreError::Parse { url, location, .. } => {
+                details.push(("URL", url.clone()));
+                details.push(("Expected", location.clone()));
+            }
+            CoreError::HashMismatch { expected, actual } => {
+                details.push(("Expected", expected.clone()));
+                details.push(("Got", actual.clone()));
+            }
+            CoreError::ExtractFailure { archive: path, .. }
+            | CoreError::WorkspaceFailure { path, .. }
+            | CoreError::InvalidMirror { path, .. }
+            | CoreError::InvalidLearningPath { path, .. }
+            | CoreError::InvalidSource { path, .. }
+            | CoreError::UnsafePath(path) => details.push(("Path", path.display().to_string())),
+            _ => {}
+        }
+
+        // The message already includes the first cause
+        let mut source = self.source().and_then(|error| error.source());
+        while let Some(error) = source {
+            details.push(("Cause", error.to_string()));
+            source = error.source();
+        }
+        details
+    }
+}
+
+impl From<std::io::Error> for CoreError {
+    fn from(error: std::io::Error) -> Self {
+        CoreError::Io(error)
+    }
+}
+
+impl From<rusqlite::Error> for CoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        CoreError::IndexFailure(error)
+    }
+}
+
+impl std::fmt::Display for CoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoreError::Io(error) => write!(f, "I/O error: {}", error),
+            CoreError::Request {
+                resource, source, ..
+            } => write!(f, "could not fetch the {}: {}", resource, source),
+            CoreError::Status {
+                resource,
+                url,
+                status,
+            } => write!(f, "{} answered {} for the {}", url, status, resource),
+            CoreError::Parse {
+                resource,
+                url,
+                location,
+            } => write!(
+                f,
+                "could not read the {} at {}, expected {}",
+                resource, url, location
+            ),
+            CoreError::UnknownChallenge(url) => write!(f, "no source knows the challenge {}", url),
+            CoreError::DownloadFailure { failed } => write!(f, "{} downloads failed", failed),
+            CoreError::Cancelled => write!(f, "cancelled"),
+            CoreError::HashMismatch { .. } => write!(
+                f,
+                "downloaded archive does not match the hash of the challenge page"
+            ),
+            CoreError::ExtractFailure { archive, source } => {
+                write!(f, "could not extract {}: {}", archive.display(), source)
+            }
+            CoreError::UnsafeArchive(reason) => write!(f, "unsafe archive: {}", reason),
+            CoreError::WorkspaceFailure { path, source } => write!(
+                f,
+                "could not set up the workspace at {}: {}",
+                path.display(),
+                source
+            ),
+            CoreError::UnsafePath(path) => {
+                write!(f, "{} leaves the directory it belongs to", path.display())
+            }
+            CoreError::InvalidConfig(error) => write!(f, "invalid configuration: {}", error),
+            CoreError::InvalidProgress(error) => write!(f, "invalid progress file: {}", error),
+            CoreError::InvalidLearningPath { path, source } => {
+                write!(f, "invalid learning path {}: {}", path.display(), source)
+            }
+            CoreError::IndexFailure(error) => write!(f, "local index error: {}", error),
+            CoreError::NoDataDirectory => write!(f, "no data directory to keep the index in"),
+            CoreError::InvalidMirror { path, source } => {
+                write!(f, "invalid mirror file {}: {}", path.display(), source)
+            }
+            CoreError::NotMirrored(url) => {
+                write!(f, "the archive of {} is not in the mirror", url)
+            }
+            CoreError::MirrorFailure { failed } => {
+                write!(f, "{} challenges could not be mirrored", failed)
+            }
+            CoreError::InvalidSource { path, source } => {
+                write!(f, "invalid sidecar {}: {}", path.display(), source)
+            }
+            CoreError::HealthCheckFailure { failed } => {
+                write!(f, "{} health checks failed", failed)
+            }
+        }
+    }
+}
+
+impl Error for CoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CoreError::Io(error) | CoreError::WorkspaceFailure { source: error, .. } => Some(error),
+            CoreError::Request { source, .. } => Some(source),
+            CoreError::ExtractFailure { source, .. } => Some(source),
+            CoreError::InvalidConfig(error)
+            | CoreError::InvalidLearningPath { source: error, .. }
+            | CoreError::InvalidSource { source: error, .. } => Some(error),
+            CoreError::InvalidProgress(error) | CoreError::InvalidMirror { source: error, .. } => {
+                Some(error)
+            }
+            CoreError::IndexFailure(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: StatusCode) -> CoreError {
+        CoreError::Status {
+            resource: Resource::Archive,
+            url: String::from("https://crackmes.one/static/crackme/abc123.zip"),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_only_temporary_statuses_are_transient() {
+        assert!(status(StatusCode::SERVICE_UNAVAILABLE).is_transient());
+        assert!(status(StatusCode::TOO_MANY_REQUESTS).is_transient());
+        assert!(!status(StatusCode::NOT_FOUND).is_transient());
+        assert!(!CoreError::UnsafeArchive(String::from("zip bomb")).is_transient());
+    }
+
+    #[test]
+    fn test_details_label_the_context_and_the_deeper_causes() {
+        assert_eq!(
+            status(StatusCode::NOT_FOUND).details(),
+            vec![
+                (
+                    "URL",
+                    String::from("https://crackmes.one/static/crackme/abc123.zip")
+                ),
+                ("Status", String::from("404 Not Found")),
+            ]
+        );
+
+        let error = CoreError::ExtractFailure {
+            archive: PathBuf::from("/tmp/abc123.zip"),
+            source: zip::result::ZipError::Io(std::io::Error::other("disk on fire")),
+        };
+        assert!(error.to_string().contains("/tmp/abc123.zip"));
+        assert_eq!(
+            error.details(),
+            vec![
+                ("Path", String::from("/tmp/abc123.zip")),
+                ("Cause", String::from("disk on fire")),
+            ]
+        );
+    }
 }
@@ -1,7 +1,11 @@
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use std::time::Duration;
 
-use crate::app::App;
+use crate::{
+    app::{App, Tab, WriteupStage},
+    progress::ProgressStatus,
+    view::SortKey,
+};
 
 pub fn handle_events(app: &mut App) -> std::io::Result<()> {
     if event::poll(Duration::from_millis(100))?
@@ -14,22 +18,226 @@ pub fn handle_events(app: &mut App) -> std::io::Result<()> {
 }
 
 fn handle_key_event(app: &mut App, key: KeyEvent) {
+    if app.show_error_popup {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+            app.dismiss_error();
+        }
+        return;
+    }
+    if app.writeup_browser.is_some() {
+        handle_writeup_key_event(app, key);
+        return;
+    }
+    if app.show_search_form {
+        handle_search_form_key_event(app, key);
+        return;
+    }
+    if app.editing_filter {
+        handle_filter_key_event(app, key);
+        return;
+    }
+    if app.tab != Tab::Challenges {
+        handle_tab_key_event(app, key);
+        return;
+    }
+
     match key.code {
         KeyCode::Char('q') | KeyCode::Esc => {
             app.quit();
         }
+        KeyCode::Tab => {
+            app.next_tab();
+        }
         KeyCode::Down | KeyCode::Char('j') => {
             app.next_challenge();
         }
         KeyCode::Up | KeyCode::Char('k') => {
             app.previous_challenge();
         }
-        KeyCode::Char('d') | KeyCode::Enter => {
+        KeyCode::Char('d') | KeyCode::Enter if app.get_selected_challenge().is_some() => {
             // This will trigger the download in main loop
-            if app.get_selected_challenge().is_some() {
-                app.trigger_download();
+            app.trigger_download();
+        }
+        KeyCode::Char('i') => {
+            app.toggle_details();
+        }
+        KeyCode::Char('w') => {
+            app.open_writeups();
+        }
+        KeyCode::Char('s') => {
+            app.open_search_form();
+        }
+        KeyCode::Char('/') => {
+            app.start_filter();
+        }
+        KeyCode::Char('r') => {
+            app.request_random(false);
+        }
+        KeyCode::Char('R') => {
+            app.request_random(true);
+        }
+        KeyCode::Char('p') => {
+            app.set_selected_progress(ProgressStatus::InProgress);
+        }
+        KeyCode::Char('x') => {
+            app.set_selected_progress(ProgressStatus::Solved);
+        }
+        KeyCode::Char('g') => {
+            app.set_selected_progress(ProgressStatus::GaveUp);
+        }
+        KeyCode::Char('n') => {
+            app.set_selected_progress(ProgressStatus::New);
+        }
+        KeyCode::Char('D') => {
+            app.toggle_downloads();
+        }
+        KeyCode::Char('J') if app.show_downloads => {
+            app.next_download();
+        }
+        KeyCode::Char('K') if app.show_downloads => {
+            app.previous_download();
+        }
+        KeyCode::Char('c') if app.show_downloads => {
+            app.cancel_selected_download();
+        }
+        KeyCode::Char(c) => {
+            if let Some(key) = SortKey::from_digit(c) {
+                app.toggle_sort(key);
             }
         }
         _ => {}
     }
 }
+
+// Keys of the read-only tabs
+fn handle_tab_key_event(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app.quit();
+        }
+        KeyCode::Tab => {
+            app.next_tab();
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.tab == Tab::Recommended => {
+            app.next_recommendation();
+        }
+        KeyCode::Up | KeyCode::Char('k') if app.tab == Tab::Recommended => {
+            app.previous_recommendation();
+        }
+        KeyCode::Enter if app.tab == Tab::Recommended => {
+            app.show_recommendation();
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.tab == Tab::Paths => {
+            app.next_stage();
+        }
+        KeyCode::Up | KeyCode::Char('k') if app.tab == Tab::Paths => {
+            app.previous_stage();
+        }
+        KeyCode::Enter if app.tab == Tab::Paths => {
+            app.open_selected_stage();
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.tab == Tab::Errors => {
+            app.next_error();
+        }
+        KeyCode::Up | KeyCode::Char('k') if app.tab == Tab::Errors => {
+            app.previous_error();
+        }
+        _ => {}
+    }
+}
+
+fn handle_writeup_key_event(app: &mut App, key: KeyEvent) {
+    let Some(stage) = app.writeup_browser.as_ref().map(|browser| &browser.stage) else {
+        return;
+    };
+
+    match (stage, key.code) {
+        (WriteupStage::List, KeyCode::Char('q') | KeyCode::Esc) => {
+            app.close_writeups();
+        }
+        (WriteupStage::List, KeyCode::Down | KeyCode::Char('j')) => {
+            app.next_writeup();
+        }
+        (WriteupStage::List, KeyCode::Up | KeyCode::Char('k')) => {
+            app.previous_writeup();
+        }
+        (WriteupStage::List, KeyCode::Enter) => {
+            app.request_reveal();
+        }
+        (WriteupStage::List | WriteupStage::Reading, KeyCode::Char('s')) => {
+            app.trigger_writeup_download();
+        }
+        (WriteupStage::ConfirmReveal, KeyCode::Char('y')) => {
+            app.confirm_reveal();
+        }
+        (WriteupStage::ConfirmReveal, _) => {
+            app.hide_writeup();
+        }
+        (WriteupStage::Reading, KeyCode::Down | KeyCode::Char('j')) => {
+            app.scroll_writeup(1);
+        }
+        (WriteupStage::Reading, KeyCode::Up | KeyCode::Char('k')) => {
+            app.scroll_writeup(-1);
+        }
+        (WriteupStage::Reading, KeyCode::Char('q') | KeyCode::Esc) => {
+            app.hide_writeup();
+        }
+        _ => {}
+    }
+}
+
+fn handle_search_form_key_event(app: &mut App, key: KeyEvent) {
+    let form = &mut app.search_form;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.close_search_form();
+        }
+        KeyCode::Enter => {
+            app.submit_search();
+        }
+        KeyCode::Tab | KeyCode::Down => {
+            form.focus_next();
+        }
+        KeyCode::BackTab | KeyCode::Up => {
+            form.focus_previous();
+        }
+        KeyCode::Left => {
+            form.cycle(-1);
+        }
+        KeyCode::Right => {
+            form.cycle(1);
+        }
+        KeyCode::Backspace => {
+            form.delete_char();
+        }
+        KeyCode::Char(c) => {
+            form.type_char(c);
+        }
+        _ => {}
+    }
+}
+
+fn handle_filter_key_event(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => {
+            app.clear_filter();
+        }
+        KeyCode::Enter => {
+            app.finish_filter();
+        }
+        KeyCode::Down => {
+            app.next_challenge();
+        }
+        KeyCode::Up => {
+            app.previous_challenge();
+        }
+        KeyCode::Backspace => {
+            app.pop_filter_char();
+        }
+        KeyCode::Char(c) => {
+            app.push_filter_char(c);
+        }
+        _ => {}
+    }
+}
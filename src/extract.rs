@@ -0,0 +1,262 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use zip::{ZipArchive, result::ZipError};
+
+use crate::errors::CoreError;
+
+/// Password used by crackmes.one for every challenge archive.
+pub const DEFAULT_PASSWORD: &str = "crackmes.one";
+
+#[derive(Clone)]
+pub struct ExtractOptions {
+    pub password: String,
+    /// Maximum number of bytes written for the whole archive.
+    pub max_total_size: u64,
+    /// Maximum uncompressed/compressed ratio allowed for a single entry.
+    pub max_ratio: u64,
+    pub max_entries: usize,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            password: String::from(DEFAULT_PASSWORD),
+            max_total_size: 512 * 1024 * 1024,
+            max_ratio: 100,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// What ended up on disk after an extraction.
+#[derive(Debug)]
+pub struct ExtractReport {
+    pub directory: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// Extracts `archive` into `destination`, decrypting entries with the
+/// configured password.
+///
+/// Entries escaping `destination` (zip-slip) or looking like a zip bomb abort
+/// the extraction with `CoreError::UnsafeArchive`, leaving `destination` as
+/// it was.
+pub fn extract_archive(
+    archive: &Path,
+    destination: &Path,
+    options: &ExtractOptions,
+) -> Result<ExtractReport, CoreError> {
+    let failure = |source: ZipError| CoreError::ExtractFailure {
+        archive: archive.to_path_buf(),
+        source,
+    };
+    let io_failure = |source: io::Error| failure(source.into());
+
+    let file = File::open(archive).map_err(io_failure)?;
+    let mut zip = ZipArchive::new(file).map_err(failure)?;
+
+    if zip.len() > options.max_entries {
+        return Err(CoreError::UnsafeArchive(format!(
+            "{} entries, at most {} are allowed",
+            zip.len(),
+            options.max_entries
+        )));
+    }
+
+    // Entries land next to the destination first and are only moved into
+    // place once the whole archive passed the checks
+    let staging = staging_dir(destination);
+    let _ = fs::remove_dir_all(&staging);
+    let result = extract_entries(&mut zip, &staging, options, &failure)
+        .and_then(|entries| move_entries(&staging, destination, entries).map_err(io_failure));
+    let _ = fs::remove_dir_all(&staging);
+
+    Ok(ExtractReport {
+        directory: destination.to_path_buf(),
+        files: result?,
+    })
+}
+
+// Extracts every entry under `staging`, returning the relative paths of the
+// directories and files it holds
+fn extract_entries(
+    zip: &mut ZipArchive<File>,
+    staging: &Path,
+    options: &ExtractOptions,
+    failure: &impl Fn(ZipError) -> CoreError,
+) -> Result<Vec<(PathBuf, bool)>, CoreError> {
+    let io_failure = |source: io::Error| failure(source.into());
+    fs::create_dir_all(staging).map_err(io_failure)?;
+
+    let mut entries = Vec::new();
+    let mut written: u64 = 0;
+
+    for index in 0..zip.len() {
+        let mut entry = zip
+            .by_index_decrypt(index, options.password.as_bytes())
+            .map_err(failure)?;
+
+        // `enclosed_name` rejects absolute paths and `..` components
+        let relative = entry.enclosed_name().ok_or_else(|| {
+            CoreError::UnsafeArchive(format!("{} leaves the destination", entry.name()))
+        })?;
+        let target = staging.join(&relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target).map_err(io_failure)?;
+            entries.push((relative, true));
+            continue;
+        }
+
+        // Sizes come straight from the headers, which may be crafted
+        let compressed = entry.compressed_size().max(1);
+        if entry.size() / compressed > options.max_ratio
+            || written.saturating_add(entry.size()) > options.max_total_size
+        {
+            return Err(CoreError::UnsafeArchive(format!(
+                "{} expands to {} bytes",
+                entry.name(),
+                entry.size()
+            )));
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(io_failure)?;
+        }
+        let mut out = File::create(&target).map_err(io_failure)?;
+
+        // Headers can lie about sizes, so the budget is enforced on the bytes
+        // actually produced as well
+        let budget = options.max_total_size - written;
+        let copied = io::copy(&mut (&mut entry).take(budget + 1), &mut out).map_err(io_failure)?;
+        if copied > budget {
+            return Err(CoreError::UnsafeArchive(format!(
+                "{} expands past its announced size",
+                entry.name()
+            )));
+        }
+
+        written += copied;
+        entries.push((relative, false));
+    }
+
+    Ok(entries)
+}
+
+// Moves the extracted entries from `staging` to `destination`, replacing
+// files of an earlier extraction, and returns the files
+fn move_entries(
+    staging: &Path,
+    destination: &Path,
+    entries: Vec<(PathBuf, bool)>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    fs::create_dir_all(destination)?;
+    for (relative, is_dir) in entries {
+        let target = destination.join(&relative);
+        if is_dir {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(staging.join(&relative), &target)?;
+        files.push(relative);
+    }
+    Ok(files)
+}
+
+// A hidden sibling of `destination`, so that moving out of it is a rename
+// on the same file system
+fn staging_dir(destination: &Path) -> PathBuf {
+    let name = destination
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    destination.with_file_name(format!(".{}.extracting", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{AesMode, unstable::write::FileOptionsExt, write::SimpleFileOptions};
+
+    fn write_archive(path: &Path, entries: &[(&str, &[u8])], options: SimpleFileOptions) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_extracts_zipcrypto_and_aes_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let encryptions = [
+            SimpleFileOptions::default().with_deprecated_encryption(DEFAULT_PASSWORD.as_bytes()),
+            SimpleFileOptions::default().with_aes_encryption(AesMode::Aes256, DEFAULT_PASSWORD),
+        ];
+
+        for (i, encryption) in encryptions.into_iter().enumerate() {
+            let archive = dir.path().join(format!("{}.zip", i));
+            write_archive(&archive, &[("bin/crackme", b"\x7fELF")], encryption);
+
+            let out = dir.path().join(i.to_string());
+            let report = extract_archive(&archive, &out, &ExtractOptions::default()).unwrap();
+            assert_eq!(report.files, vec![PathBuf::from("bin/crackme")]);
+            assert_eq!(fs::read(out.join("bin/crackme")).unwrap(), b"\x7fELF");
+        }
+    }
+
+    #[test]
+    fn test_rejects_entries_escaping_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("slip.zip");
+        write_archive(
+            &archive,
+            &[("../evil", b"pwned")],
+            SimpleFileOptions::default(),
+        );
+
+        let out = dir.path().join("out");
+        let result = extract_archive(&archive, &out, &ExtractOptions::default());
+        assert!(matches!(result, Err(CoreError::UnsafeArchive(_))));
+        assert!(!dir.path().join("evil").exists());
+    }
+
+    #[test]
+    fn test_failed_extraction_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("slip.zip");
+        write_archive(
+            &archive,
+            &[("readme.txt", b"hello"), ("../evil", b"pwned")],
+            SimpleFileOptions::default(),
+        );
+
+        let out = dir.path().join("out");
+        let result = extract_archive(&archive, &out, &ExtractOptions::default());
+        assert!(matches!(result, Err(CoreError::UnsafeArchive(_))));
+        assert!(!out.join("readme.txt").exists());
+        assert!(!staging_dir(&out).exists());
+    }
+
+    #[test]
+    fn test_rejects_highly_compressed_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bomb.zip");
+        let zeros = vec![0u8; 1024 * 1024];
+        write_archive(&archive, &[("zeros", &zeros)], SimpleFileOptions::default());
+
+        let out = dir.path().join("out");
+        let result = extract_archive(&archive, &out, &ExtractOptions::default());
+        assert!(matches!(result, Err(CoreError::UnsafeArchive(_))));
+    }
+}
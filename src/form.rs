@@ -0,0 +1,265 @@
+use clap::ValueEnum;
+
+use crate::{
+    cli::{ArchArg, LanguageArg, PlatformArg, SearchArgs},
+    search::{Arch, Language, Platform, SearchParams},
+};
+
+// Bounds of the difficulty and quality ratings accepted by the search
+const RATING_MIN: i32 = 1;
+const RATING_MAX: i32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchField {
+    Name,
+    Author,
+    Query,
+    DifficultyMin,
+    DifficultyMax,
+    QualityMin,
+    QualityMax,
+    Language,
+    Arch,
+    Platform,
+}
+
+impl SearchField {
+    pub const ALL: [SearchField; 10] = [
+        SearchField::Name,
+        SearchField::Author,
+        SearchField::Query,
+        SearchField::DifficultyMin,
+        SearchField::DifficultyMax,
+        SearchField::QualityMin,
+        SearchField::QualityMax,
+        SearchField::Language,
+        SearchField::Arch,
+        SearchField::Platform,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchField::Name => "Name",
+            SearchField::Author => "Author",
+            SearchField::Query => "Text (offline)",
+            SearchField::DifficultyMin => "Difficulty min",
+            SearchField::DifficultyMax => "Difficulty max",
+            SearchField::QualityMin => "Quality min",
+            SearchField::QualityMax => "Quality max",
+            SearchField::Language => "Language",
+            SearchField::Arch => "Arch",
+            SearchField::Platform => "Platform",
+        }
+    }
+
+    /// Whether the field is typed in rather than cycled through.
+    pub fn is_text(self) -> bool {
+        matches!(
+            self,
+            SearchField::Name | SearchField::Author | SearchField::Query
+        )
+    }
+}
+
+/// State of the search overlay, turned into `SearchParams` on submit.
+#[derive(Debug, Clone)]
+pub struct SearchForm {
+    pub name: String,
+    pub author: String,
+    /// Full-text query, only understood by the local index.
+    pub query: String,
+    pub difficulty_range: [i32; 2],
+    pub quality_range: [i32; 2],
+    pub language: Option<Language>,
+    pub arch: Option<Arch>,
+    pub platform: Option<Platform>,
+    pub focused: usize,
+}
+
+impl SearchForm {
+    /// Prefills the form with the filters given on the command line.
+    pub fn from_args(args: &SearchArgs) -> Self {
+        Self::from_params(args.to_search_params(String::new()))
+    }
+
+    pub fn from_params(params: SearchParams) -> Self {
+        Self {
+            name: params.name.unwrap_or_default(),
+            author: params.author.unwrap_or_default(),
+            query: String::new(),
+            difficulty_range: params.difficulty_range.unwrap_or([RATING_MIN, RATING_MAX]),
+            quality_range: params.quality_range.unwrap_or([RATING_MIN, RATING_MAX]),
+            language: params.language,
+            arch: params.arch,
+            platform: params.platform,
+            focused: 0,
+        }
+    }
+
+    pub fn to_search_params(&self, token: String) -> SearchParams {
+        let text = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        SearchParams {
+            name: text(&self.name),
+            author: text(&self.author),
+            difficulty_range: Some(self.difficulty_range),
+            quality_range: Some(self.quality_range),
+            language: self.language.clone(),
+            arch: self.arch.clone(),
+            platform: self.platform.clone(),
+            query: text(&self.query),
+            token,
+        }
+    }
+
+    pub fn focused_field(&self) -> SearchField {
+        SearchField::ALL[self.focused]
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focused = (self.focused + 1) % SearchField::ALL.len();
+    }
+
+    pub fn focus_previous(&mut self) {
+        let count = SearchField::ALL.len();
+        self.focused = (self.focused + count - 1) % count;
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        match self.focused_field() {
+            SearchField::Name => self.name.push(c),
+            SearchField::Author => self.author.push(c),
+            SearchField::Query => self.query.push(c),
+            _ => {}
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        match self.focused_field() {
+            SearchField::Name => {
+                self.name.pop();
+            }
+            SearchField::Author => {
+                self.author.pop();
+            }
+            SearchField::Query => {
+                self.query.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves a range bound or dropdown of the focused field by `step`.
+    pub fn cycle(&mut self, step: i32) {
+        match self.focused_field() {
+            SearchField::Name | SearchField::Author | SearchField::Query => {}
+            SearchField::DifficultyMin => step_range(&mut self.difficulty_range, 0, step),
+            SearchField::DifficultyMax => step_range(&mut self.difficulty_range, 1, step),
+            SearchField::QualityMin => step_range(&mut self.quality_range, 0, step),
+            SearchField::QualityMax => step_range(&mut self.quality_range, 1, step),
+            SearchField::Language => {
+                self.language =
+                    cycle_choice(self.language.as_ref(), LanguageArg::value_variants(), step)
+            }
+            SearchField::Arch => {
+                self.arch = cycle_choice(self.arch.as_ref(), ArchArg::value_variants(), step)
+            }
+            SearchField::Platform => {
+                self.platform =
+                    cycle_choice(self.platform.as_ref(), PlatformArg::value_variants(), step)
+            }
+        }
+    }
+
+    /// Text shown for a field, `Any` standing for an unset dropdown.
+    pub fn value(&self, field: SearchField) -> String {
+        let choice = |value: Option<String>| value.unwrap_or_else(|| String::from("Any"));
+        match field {
+            SearchField::Name => self.name.clone(),
+            SearchField::Author => self.author.clone(),
+            SearchField::Query => self.query.clone(),
+            SearchField::DifficultyMin => self.difficulty_range[0].to_string(),
+            SearchField::DifficultyMax => self.difficulty_range[1].to_string(),
+            SearchField::QualityMin => self.quality_range[0].to_string(),
+            SearchField::QualityMax => self.quality_range[1].to_string(),
+            SearchField::Language => choice(self.language.as_ref().map(|l| l.to_string())),
+            SearchField::Arch => choice(self.arch.as_ref().map(|a| a.to_string())),
+            SearchField::Platform => choice(self.platform.as_ref().map(|p| p.to_string())),
+        }
+    }
+}
+
+// Keeps min <= max by dragging the other bound along
+fn step_range(range: &mut [i32; 2], bound: usize, step: i32) {
+    range[bound] = (range[bound] + step).clamp(RATING_MIN, RATING_MAX);
+    if range[0] > range[1] {
+        range[1 - bound] = range[bound];
+    }
+}
+
+// Cycles through `None` followed by every choice of the matching CLI enum
+fn cycle_choice<A, T>(current: Option<&T>, variants: &[A], step: i32) -> Option<T>
+where
+    A: Copy + Into<T>,
+    T: ToString,
+{
+    let choices: Vec<T> = variants.iter().map(|&variant| variant.into()).collect();
+    let position = current
+        .and_then(|current| {
+            choices
+                .iter()
+                .position(|choice| choice.to_string() == current.to_string())
+        })
+        .map(|index| index as i32 + 1)
+        .unwrap_or(0);
+    let count = choices.len() as i32 + 1;
+    let next = (position + step).rem_euclid(count);
+
+    if next == 0 {
+        None
+    } else {
+        choices.into_iter().nth(next as usize - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_dropdown_wraps_through_any() {
+        let mut form = SearchForm::from_args(&SearchArgs::default());
+        form.focused = SearchField::ALL
+            .iter()
+            .position(|&field| field == SearchField::Arch)
+            .unwrap();
+
+        form.cycle(1);
+        assert_eq!(form.value(SearchField::Arch), "x86");
+        form.cycle(-1);
+        assert_eq!(form.value(SearchField::Arch), "Any");
+        form.cycle(-1);
+        assert_eq!(form.value(SearchField::Arch), "other");
+    }
+
+    #[test]
+    fn test_range_bounds_stay_ordered() {
+        let mut range = [2, 3];
+        step_range(&mut range, 0, 2);
+        assert_eq!(range, [4, 4]);
+        step_range(&mut range, 1, -3);
+        assert_eq!(range, [1, 1]);
+        step_range(&mut range, 1, 10);
+        assert_eq!(range, [1, 6]);
+    }
+
+    #[test]
+    fn test_to_search_params_drops_blank_text() {
+        let mut form = SearchForm::from_args(&SearchArgs::default());
+        form.name = String::from("  ");
+        form.author = String::from("bob");
+        let params = form.to_search_params(String::from("token"));
+        assert!(params.name.is_none());
+        assert_eq!(params.author.as_deref(), Some("bob"));
+        assert_eq!(params.difficulty_range, Some([1, 6]));
+    }
+}
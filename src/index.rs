@@ -0,0 +1,432 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter, types::Value};
+
+use crate::{
+    challenge::{Challenge, UNRATED},
+    errors::CoreError,
+    progress,
+    search::{Arch, Language, Platform, SearchParams},
+    view::parse_date,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS challenges (
+        url TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        author TEXT NOT NULL,
+        language TEXT NOT NULL,
+        arch TEXT NOT NULL,
+        platform TEXT NOT NULL,
+        difficulty REAL NOT NULL,
+        quality REAL NOT NULL,
+        upload_date TEXT,
+        uploaded_key INTEGER,
+        description TEXT,
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS challenges_text USING fts5(
+        url UNINDEXED, name, author, description
+    );
+";
+
+/// Every challenge we have seen, kept in `<data dir>/cracked/index.sqlite`
+/// so that searches also work offline.
+pub struct ChallengeIndex {
+    connection: Connection,
+}
+
+impl ChallengeIndex {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cracked").join("index.sqlite"))
+    }
+
+    /// Opens the index at its default location, creating it if needed.
+    pub fn open_default() -> Result<Self, CoreError> {
+        let path = Self::path().ok_or(CoreError::NoDataDirectory)?;
+        Self::open(&path)
+    }
+
+    pub fn open(path: &Path) -> Result<Self, CoreError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::setup(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, CoreError> {
+        Self::setup(Connection::open_in_memory()?)
+    }
+
+    fn setup(connection: Connection) -> Result<Self, CoreError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Adds or refreshes challenges from a results page and returns how many
+    /// were not in the index yet.
+    pub fn record(&mut self, challenges: &[Challenge]) -> Result<usize, CoreError> {
+        let now = progress::now() as i64;
+        let transaction = self.connection.transaction()?;
+        let mut added = 0;
+
+        for challenge in challenges {
+            let known: Option<Option<String>> = transaction
+                .query_row(
+                    "SELECT description FROM challenges WHERE url = ?1",
+                    [&challenge.url],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if known.is_none() {
+                added += 1;
+            }
+
+            transaction.execute(
+                "INSERT INTO challenges (url, name, author, language, arch, platform,
+                        difficulty, quality, upload_date, uploaded_key, first_seen, last_seen)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)
+                     ON CONFLICT(url) DO UPDATE SET name = ?2, author = ?3, language = ?4,
+                        arch = ?5, platform = ?6, difficulty = ?7, quality = ?8,
+                        upload_date = COALESCE(?9, upload_date),
+                        uploaded_key = COALESCE(?10, uploaded_key), last_seen = ?11",
+                params![
+                    challenge.url,
+                    challenge.name,
+                    challenge.author,
+                    challenge.language.to_string(),
+                    challenge.arch.to_string(),
+                    challenge.platform.to_string(),
+                    challenge.difficulty,
+                    challenge.quality,
+                    challenge.upload_date,
+                    challenge.upload_date.as_deref().and_then(uploaded_key),
+                    now,
+                ],
+            )?;
+            update_text(
+                &transaction,
+                &challenge.url,
+                &challenge.name,
+                &challenge.author,
+                known.flatten().as_deref(),
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(added)
+    }
+
+    /// Stores the description of a challenge already in the index.
+    pub fn set_description(&mut self, url: &str, description: &str) -> Result<(), CoreError> {
+        let names: Option<(String, String)> = self
+            .connection
+            .query_row(
+                "SELECT name, author FROM challenges WHERE url = ?1",
+                [url],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((name, author)) = names else {
+            return Ok(());
+        };
+
+        self.connection.execute(
+            "UPDATE challenges SET description = ?2 WHERE url = ?1",
+            params![url, description],
+        )?;
+        update_text(&self.connection, url, &name, &author, Some(description))
+    }
+
+    pub fn description(&self, url: &str) -> Option<String> {
+        self.connection
+            .query_row(
+                "SELECT description FROM challenges WHERE url = ?1",
+                [url],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten()
+            .flatten()
+    }
+
+    pub fn challenge(&self, url: &str) -> Option<Challenge> {
+        self.connection
+            .query_row(
+                "SELECT url, name, author, language, arch, platform, difficulty, quality,
+                    upload_date
+                 FROM challenges WHERE url = ?1",
+                [url],
+                challenge_from_row,
+            )
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    /// Same filters as the site search, plus the full-text query of `params`
+    /// over names, authors and descriptions. Newest uploads come first.
+    pub fn search(&self, params: &SearchParams) -> Result<Vec<Challenge>, CoreError> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        let mut condition = |sql: &str, value: Value| {
+            values.push(value);
+            conditions.push(sql.replace('?', &format!("?{}", values.len())));
+        };
+
+        if let Some(name) = params.name.as_deref().filter(|name| !name.is_empty()) {
+            condition("name LIKE ? ESCAPE '\\'", Value::Text(like_pattern(name)));
+        }
+        if let Some(author) = params.author.as_deref().filter(|author| !author.is_empty()) {
+            condition(
+                "author LIKE ? ESCAPE '\\'",
+                Value::Text(like_pattern(author)),
+            );
+        }
+        if let Some([min, max]) = params.difficulty_range {
+            condition("difficulty >= ?", Value::Real(min as f64));
+            condition("difficulty <= ?", Value::Real(max as f64));
+        }
+        if let Some([min, max]) = params.quality_range {
+            // Unrated challenges match any quality range, as in the site search
+            condition(
+                &format!("(quality >= ? OR quality = {})", UNRATED),
+                Value::Real(min as f64),
+            );
+            condition("quality <= ?", Value::Real(max as f64));
+        }
+        // Unknown labels all count as other, which is filtered below
+        if let Some(language) = &params.language
+            && !matches!(language, Language::Other(_))
+        {
+            condition("language = ?", Value::Text(language.to_string()));
+        }
+        if let Some(arch) = &params.arch
+            && !matches!(arch, Arch::Other(_))
+        {
+            condition("arch = ?", Value::Text(arch.to_string()));
+        }
+        if let Some(platform) = &params.platform
+            && !matches!(platform, Platform::Other(_))
+        {
+            condition("platform = ?", Value::Text(platform.to_string()));
+        }
+        if let Some(query) = params.query.as_deref().and_then(match_expression) {
+            condition(
+                "url IN (SELECT url FROM challenges_text WHERE challenges_text MATCH ?)",
+                Value::Text(query),
+            );
+        }
+
+        let mut sql = String::from(
+            "SELECT url, name, author, language, arch, platform, difficulty, quality, upload_date
+             FROM challenges",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY uploaded_key DESC NULLS LAST, first_seen DESC");
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let mut challenges: Vec<Challenge> = statement
+            .query_map(params_from_iter(values), challenge_from_row)
+            .and_then(|rows| rows.collect())?;
+        challenges.retain(|challenge| params.matches(challenge));
+        Ok(challenges)
+    }
+
+    pub fn len(&self) -> usize {
+        self.connection
+            .query_row("SELECT COUNT(*) FROM challenges", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count as usize)
+            .unwrap_or(0)
+    }
+}
+
+fn update_text(
+    connection: &Connection,
+    url: &str,
+    name: &str,
+    author: &str,
+    description: Option<&str>,
+) -> Result<(), CoreError> {
+    connection
+        .execute("DELETE FROM challenges_text WHERE url = ?1", [url])
+        .and_then(|_| {
+            connection.execute(
+                "INSERT INTO challenges_text (url, name, author, description)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![url, name, author, description],
+            )
+        })
+        .map(|_| ())
+        .map_err(CoreError::from)
+}
+
+fn challenge_from_row(row: &Row) -> rusqlite::Result<Challenge> {
+    Ok(Challenge {
+        url: row.get(0)?,
+        name: row.get(1)?,
+        author: row.get(2)?,
+        language: Language::from(row.get::<_, String>(3)?),
+        arch: Arch::from(row.get::<_, String>(4)?),
+        platform: Platform::from(row.get::<_, String>(5)?),
+        difficulty: row.get(6)?,
+        quality: row.get(7)?,
+        upload_date: row.get(8)?,
+        writeups: None,
+    })
+}
+
+// Upload date as a number that sorts chronologically
+fn uploaded_key(date: &str) -> Option<i64> {
+    parse_date(date).map(|(year, month, day, minutes)| {
+        ((year as i64 * 100 + month as i64) * 100 + day as i64) * 10_000 + minutes as i64
+    })
+}
+
+// Wraps `text` for a substring `LIKE ... ESCAPE '\'`, so that `%` and `_`
+// typed by the user match literally
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+// Quotes every word of the query so that user input never breaks the FTS
+// syntax, each word matching as a prefix
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(id: &str, author: &str, difficulty: f32, date: &str) -> Challenge {
+        Challenge {
+            author: String::from(author),
+            difficulty,
+            upload_date: Some(String::from(date)),
+            ..Challenge::sample(id)
+        }
+    }
+
+    fn params() -> SearchParams {
+        SearchParams {
+            name: None,
+            author: None,
+            difficulty_range: Some([1, 6]),
+            quality_range: Some([1, 6]),
+            language: None,
+            arch: None,
+            platform: None,
+            query: None,
+            token: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_counts_new_challenges() {
+        let mut index = ChallengeIndex::open_in_memory().unwrap();
+        let first = challenge("first", "alice", 1.0, "2024-01-01 10:00");
+        let second = challenge("second", "bob", 3.0, "2024-02-01 10:00");
+
+        assert_eq!(index.record(std::slice::from_ref(&first)).unwrap(), 1);
+        assert_eq!(index.record(&[first, second]).unwrap(), 1);
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.challenge("/crackme/second").map(|c| c.author),
+            Some(String::from("bob"))
+        );
+    }
+
+    #[test]
+    fn test_search_filters_and_orders_by_upload_date() {
+        let mut index = ChallengeIndex::open_in_memory().unwrap();
+        index
+            .record(&[
+                challenge("old_keygen", "alice", 1.5, "01:00 PM 01/02/2023"),
+                challenge("new_keygen", "bob", 2.5, "2024-05-01 09:00"),
+                challenge("vm", "alice", 4.0, "2024-06-01 09:00"),
+            ])
+            .unwrap();
+
+        let names = |challenges: Vec<Challenge>| -> Vec<String> {
+            challenges.into_iter().map(|c| c.name).collect()
+        };
+        let mut filter = params();
+        filter.name = Some(String::from("keygen"));
+        assert_eq!(
+            names(index.search(&filter).unwrap()),
+            vec!["new_keygen", "old_keygen"]
+        );
+
+        filter.difficulty_range = Some([1, 2]);
+        assert_eq!(names(index.search(&filter).unwrap()), vec!["old_keygen"]);
+    }
+
+    #[test]
+    fn test_name_wildcards_match_literally() {
+        let mut index = ChallengeIndex::open_in_memory().unwrap();
+        index
+            .record(&[
+                challenge("crack_me", "alice", 2.0, "2024-01-01"),
+                challenge("crackme", "bob", 2.0, "2024-01-02"),
+                challenge("100%", "carol", 2.0, "2024-01-03"),
+            ])
+            .unwrap();
+
+        let mut filter = params();
+        filter.name = Some(String::from("k_m"));
+        let found = index.search(&filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "crack_me");
+
+        filter.name = Some(String::from("%"));
+        let found = index.search(&filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "100%");
+        assert_eq!(like_pattern("a%b_c\\"), "%a\\%b\\_c\\\\%");
+    }
+
+    #[test]
+    fn test_full_text_search_covers_descriptions() {
+        let mut index = ChallengeIndex::open_in_memory().unwrap();
+        index
+            .record(&[
+                challenge("maze", "alice", 2.0, "2024-01-01"),
+                challenge("crypto", "bob", 2.0, "2024-01-02"),
+            ])
+            .unwrap();
+        index
+            .set_description("/crackme/maze", "Walk through a virtual machine")
+            .unwrap();
+
+        let query = |text: &str| SearchParams {
+            query: Some(text.to_string()),
+            ..params()
+        };
+        let found = index.search(&query("virt")).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "maze");
+        assert_eq!(index.search(&query("bob")).unwrap()[0].name, "crypto");
+        assert!(index.search(&query("\"unbalanced")).unwrap().is_empty());
+        assert_eq!(
+            index.description("/crackme/maze").as_deref(),
+            Some("Walk through a virtual machine")
+        );
+    }
+}
@@ -8,21 +8,130 @@ use std::io;
 mod app;
 mod challenge;
 mod cli;
+mod commands;
+mod config;
+mod curriculum;
+mod doctor;
+mod downloads;
 mod errors;
 mod event;
+mod extract;
+mod form;
+mod index;
+mod mirror;
+#[cfg(test)]
+mod mock_server;
+mod output;
+mod paths;
+mod progress;
+mod rating;
+mod recommend;
+mod roulette;
 mod search;
+mod source;
+mod stats;
 mod ui;
+mod view;
+mod workspace;
 
 use app::App;
+use challenge::Challenge;
 use clap::Parser;
-use cli::Cli;
-use search::{get_challenge_list, get_search_token};
+use cli::{Cli, Command, DownloadArgs, IndexArgs, SearchArgs};
+use config::Config;
+use downloads::DownloadManager;
+use errors::CoreError;
+use form::SearchForm;
+use index::ChallengeIndex;
+use progress::ProgressStore;
+use roulette::RANDOM_PAGES;
+use search::SearchParams;
+use source::Sources;
+use std::sync::Arc;
+use tokio::{
+    sync::Semaphore,
+    task::{JoinHandle, JoinSet},
+};
+
+// Challenge pages fetched at the same time while loading a stage
+const MAX_CONCURRENT_STAGE_FETCHES: usize = 4;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse CLI arguments
     let cli = Cli::parse();
 
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration file: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match &cli.command {
+        None => run_tui(&cli.search, &cli.download, &cli.index, &config).await,
+        Some(Command::Tui {
+            search,
+            download,
+            index,
+        }) => run_tui(search, download, index, &config).await,
+        Some(Command::Search {
+            search,
+            pages,
+            format,
+            index,
+        }) => commands::search(search, index, *pages, *format, &config).await,
+        Some(Command::Sync { full }) => commands::sync(*full).await,
+        Some(Command::Mirror {
+            directory,
+            concurrency,
+            delay,
+        }) => commands::mirror(directory, *concurrency, *delay).await,
+        Some(Command::Recommend {
+            search,
+            pages,
+            count,
+            format,
+        }) => commands::recommend(search, *pages, *count, *format, &config).await,
+        Some(Command::Random {
+            search,
+            pages,
+            weighted,
+            format,
+        }) => commands::random(search, *pages, *weighted, *format, &config).await,
+        Some(Command::Info { id }) => commands::info(id, &config).await,
+        Some(Command::Doctor) => commands::doctor().await,
+        Some(Command::Download {
+            id,
+            search,
+            download,
+            pages,
+        }) => commands::download(id.as_deref(), search, download, *pages, &config).await,
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn run_tui(
+    search: &SearchArgs,
+    download: &DownloadArgs,
+    index_args: &IndexArgs,
+    config: &Config,
+) -> Result<(), CoreError> {
+    let sources = Arc::new(commands::open_sources(index_args, config)?);
+    let mut downloads = DownloadManager::new(download.to_download_options(config), sources.clone());
+    let progress = ProgressStore::load()?;
+    // Online, the index only caches what we see and may well be unavailable
+    let mut index = match index_args.is_offline() {
+        true => None,
+        false => ChallengeIndex::open_default().ok(),
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -30,61 +139,268 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Get tokens
-    let (cookie_token, html_token) = match get_search_token().await {
-        Ok(tokens) => tokens,
-        Err(_) => {
-            cleanup_terminal(&mut terminal)?;
-            eprintln!("Failed to get search tokens");
-            return Ok(());
-        }
-    };
-
     // Create app
     let mut app = App::new();
+    app.search_form = SearchForm::from_args(search);
+    app.search_form.query = index_args.query.clone().unwrap_or_default();
+    app.set_progress(progress);
+    if let Some(directory) = config
+        .paths_dir
+        .clone()
+        .or_else(curriculum::default_paths_dir)
+    {
+        match curriculum::load_paths(&directory) {
+            Ok((paths, errors)) => {
+                app.paths = paths;
+                for error in &errors {
+                    app.report_error("Skipped a learning path", error);
+                }
+            }
+            Err(error) => app.report_error(
+                &format!("Cannot read the learning paths in {}", directory.display()),
+                &error,
+            ),
+        }
+    }
 
     // Load initial challenges with CLI parameters
-    let params = cli.to_search_params(html_token.clone());
+    let mut cursor = sources.cursor(SearchParams {
+        query: index_args.query.clone(),
+        ..search.to_search_params(String::new())
+    });
 
-    // Debug: print search params
-
-    match get_challenge_list(params, cookie_token.clone()).await {
-        Ok(challenges) => {
-            if challenges.is_empty() {
-                app.set_status(String::from("No challenges found matching criteria"));
-            } else {
-                app.set_challenges(challenges);
+    match cursor.next_page().await {
+        Ok(Some(page)) if !page.challenges.is_empty() => {
+            record(&mut index, &page.challenges);
+            let warning = page.warning();
+            app.set_challenges(page.challenges);
+            app.set_pagination(page.page, page.has_next);
+            if let Some(warning) = warning {
+                app.set_status(warning);
             }
         }
+        Ok(page) => {
+            let warning = page.and_then(|page| page.warning());
+            app.set_status(
+                warning.unwrap_or_else(|| String::from("No challenges found matching criteria")),
+            );
+        }
         Err(e) => {
             cleanup_terminal(&mut terminal)?;
-            eprintln!("Failed to load challenges: {:?}", e);
-            return Ok(());
+            return Err(e);
         }
     }
 
+    let mut stage_loading: Option<JoinHandle<_>> = None;
+
     // Main loop
     loop {
         terminal.draw(|f| ui::render(f, &app))?;
 
         event::handle_events(&mut app)?;
 
-        // Handle download request
+        // Queue the selected challenge, the download runs in the background
         if app.should_download {
-            if let Some(challenge) = app.get_selected_challenge() {
-                // app.set_status(format!("Downloading {}...", challenge.name));
-                terminal.draw(|f| ui::render(f, &app))?;
+            if let Some(challenge) = app.get_selected_challenge().cloned() {
+                let id = downloads.enqueue(challenge.clone());
+                app.add_download(id, challenge);
+            }
+            app.reset_download_flag();
+        }
+
+        if let Some(id) = app.cancel_request.take() {
+            downloads.cancel(id);
+        }
+
+        for event in downloads.poll_events() {
+            app.apply_download_event(event);
+        }
+
+        // Fetch the selected challenge page for the detail pane
+        if let Some(url) = app.details_to_fetch() {
+            app.set_status(String::from("Fetching challenge details..."));
+            terminal.draw(|f| ui::render(f, &app))?;
+
+            let source = sources.for_url(&url);
+            match source.details(&url).await {
+                Ok(details) => {
+                    if let (Some(index), Some(description)) = (&mut index, &details.description) {
+                        let _ = index.set_description(&url, description);
+                    }
+                    app.cache_details(url, details);
+                    app.set_status(String::from("Challenge details loaded"));
+                }
+                Err(error) => {
+                    app.mark_details_failed(url);
+                    app.report_error(
+                        &format!("Failed to fetch challenge details from {}", source.name()),
+                        &error,
+                    );
+                }
+            }
+        }
+
+        // Fetch a writeup once its reveal has been confirmed
+        if app
+            .writeup_browser
+            .as_ref()
+            .is_some_and(|browser| browser.should_fetch)
+            && let Some(writeup) = app.get_selected_writeup().cloned()
+        {
+            app.set_status(format!("Fetching writeup by {}...", writeup.author));
+            terminal.draw(|f| ui::render(f, &app))?;
+
+            let challenge_url = app
+                .writeup_browser
+                .as_ref()
+                .map(|browser| browser.challenge_url.clone())
+                .unwrap_or_default();
+            let text = sources
+                .for_url(&challenge_url)
+                .writeup_text(&challenge_url, &writeup)
+                .await;
+            match text {
+                Ok(content) => {
+                    app.set_writeup_content(content);
+                    app.set_status(format!("Writeup by {}", writeup.author));
+                }
+                // Binary writeups are expected, they only need downloading
+                Err(CoreError::Parse { .. }) => {
+                    app.hide_writeup();
+                    app.set_status(String::from(
+                        "Cannot display this writeup, press s to download it",
+                    ));
+                }
+                Err(error) => {
+                    app.hide_writeup();
+                    app.report_error(
+                        &format!("Failed to fetch writeup by {}", writeup.author),
+                        &error,
+                    );
+                }
+            }
+        }
 
-                match challenge.download().await {
-                    Ok(_) => {
-                        app.set_status(format!("Successfully downloaded {}.zip", challenge.name));
+        // Save the selected writeup next to the challenges
+        if let Some(browser) = app.writeup_browser.as_mut()
+            && browser.should_download
+        {
+            browser.should_download = false;
+            if let (Some(writeup), Some(challenge)) =
+                (app.get_selected_writeup(), app.get_selected_challenge())
+            {
+                let directory = &downloads.options().output_dir;
+                let result = sources
+                    .for_url(&challenge.url)
+                    .download_writeup(challenge, writeup, directory)
+                    .await;
+                match result {
+                    Ok(path) => {
+                        app.set_status(format!("Successfully downloaded {}", path.display()))
                     }
-                    Err(_) => {
-                        app.set_status(format!("Failed to download {}", challenge.name));
+                    Err(error) => {
+                        let context = format!("Failed to download writeup by {}", writeup.author);
+                        app.report_error(&context, &error);
                     }
                 }
             }
-            app.reset_download_flag();
+        }
+
+        // Replace the list with the results of the search form
+        if app.should_search {
+            app.should_search = false;
+            // A stage still loading would replace the results of this search
+            if let Some(loading) = stage_loading.take() {
+                loading.abort();
+            }
+            app.set_status(String::from("Searching..."));
+            terminal.draw(|f| ui::render(f, &app))?;
+
+            let mut search_cursor = sources.cursor(app.search_form.to_search_params(String::new()));
+            match search_cursor.next_page().await {
+                Ok(Some(page)) => {
+                    cursor = search_cursor;
+                    record(&mut index, &page.challenges);
+                    let warning = page.warning();
+                    app.set_challenges(page.challenges);
+                    app.set_pagination(page.page, page.has_next);
+                    if let Some(warning) = warning {
+                        app.set_status(warning);
+                    }
+                }
+                Ok(None) => {
+                    cursor = search_cursor;
+                    app.set_challenges(Vec::new());
+                }
+                Err(error) => {
+                    app.report_error("Search failed, the list was left unchanged", &error);
+                }
+            }
+        }
+
+        // Replace the list with the challenges of a learning path stage
+        // Fetched in the background, the UI keeps running meanwhile
+        if let Some(urls) = app.stage_to_load.take() {
+            app.set_status(String::from("Loading stage challenges..."));
+            if let Some(previous) =
+                stage_loading.replace(tokio::spawn(load_stage(sources.clone(), urls)))
+            {
+                previous.abort();
+            }
+        }
+        if let Some(loading) = stage_loading.take_if(|loading| loading.is_finished())
+            && let Ok((challenges, errors)) = loading.await
+        {
+            record(&mut index, &challenges);
+            app.set_challenges(challenges);
+            if let Some(error) = errors.last() {
+                let context = format!("Failed to load {} stage challenges", errors.len());
+                app.report_error(&context, error);
+            }
+        }
+
+        // Load a few pages before picking so that older challenges can come up
+        if let Some(weighted) = app.random_request.take() {
+            while app.has_more_pages && cursor.pages_loaded() < RANDOM_PAGES {
+                app.set_status(format!("Loading page {}...", cursor.pages_loaded() + 1));
+                terminal.draw(|f| ui::render(f, &app))?;
+                match cursor.next_page().await {
+                    Ok(Some(page)) => {
+                        record(&mut index, &page.challenges);
+                        app.append_page(page.challenges, page.page, page.has_next)
+                    }
+                    Ok(None) => app.set_pagination(cursor.pages_loaded(), false),
+                    Err(error) => {
+                        app.report_error("Failed to load more challenges", &error);
+                        break;
+                    }
+                }
+            }
+            app.pick_random(weighted);
+        }
+
+        // Fetch the next results page once the selection nears the end
+        if app.should_load_more {
+            app.set_status(format!("Loading page {}...", cursor.pages_loaded() + 1));
+            terminal.draw(|f| ui::render(f, &app))?;
+
+            match cursor.next_page().await {
+                Ok(Some(page)) => {
+                    record(&mut index, &page.challenges);
+                    let warning = page.warning();
+                    app.append_page(page.challenges, page.page, page.has_next);
+                    if let Some(warning) = warning {
+                        app.set_status(warning);
+                    }
+                }
+                Ok(None) => {
+                    app.set_pagination(cursor.pages_loaded(), false);
+                }
+                Err(error) => {
+                    app.finish_loading_more();
+                    app.report_error("Failed to load more challenges", &error);
+                }
+            }
         }
 
         if app.should_quit {
@@ -97,9 +413,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn cleanup_terminal(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-) -> Result<(), Box<dyn std::error::Error>> {
+// Fetches the challenges of a stage concurrently, in the order of `urls`,
+// along with the errors of those that could not be fetched
+async fn load_stage(sources: Arc<Sources>, urls: Vec<String>) -> (Vec<Challenge>, Vec<CoreError>) {
+    let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_STAGE_FETCHES));
+    let mut tasks = JoinSet::new();
+    for (position, url) in urls.into_iter().enumerate() {
+        let (sources, slots) = (sources.clone(), slots.clone());
+        tasks.spawn(async move {
+            let _permit = slots.acquire().await;
+            (position, sources.for_url(&url).challenge(&url).await)
+        });
+    }
+
+    let mut loaded = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((position, Ok(challenge))) => loaded.push((position, challenge)),
+            Ok((_, Err(error))) => errors.push(error),
+            Err(_) => {}
+        }
+    }
+    loaded.sort_by_key(|(position, _)| *position);
+    (
+        loaded.into_iter().map(|(_, challenge)| challenge).collect(),
+        errors,
+    )
+}
+
+// Best effort, the index is only a cache while online
+fn record(index: &mut Option<ChallengeIndex>, challenges: &[Challenge]) {
+    if let Some(index) = index {
+        let _ = index.record(challenges);
+    }
+}
+
+fn cleanup_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
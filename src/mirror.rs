@@ -0,0 +1,335 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    challenge::{Challenge, ChallengeDetails, Writeup},
+    errors::{CoreError, Resource},
+    index::ChallengeIndex,
+    paths::sanitize_file_name,
+    search::{BASE_URL, absolute_url, get_challenge_details, html_to_text, send},
+};
+
+const STATE_FILE: &str = "state.json";
+const INDEX_FILE: &str = "index.sqlite";
+const CHALLENGES_DIR: &str = "challenges";
+const CHALLENGE_FILE: &str = "challenge.json";
+// Written last, so its presence means the challenge is fully mirrored
+const DETAILS_FILE: &str = "details.json";
+const WRITEUPS_DIR: &str = "writeups";
+
+/// A copy of the catalogue on disk, laid out as:
+///
+/// ```text
+/// <root>/
+/// ├── state.json          # where the crawl stands
+/// ├── index.sqlite        # same index as --offline uses
+/// └── challenges/<id>/
+///     ├── challenge.json  # search result metadata
+///     ├── details.json    # challenge page, description and writeup list
+///     ├── <id>.zip
+///     └── writeups/
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    root: PathBuf,
+}
+
+/// Progress of the catalogue listing, so that an interrupted crawl picks up
+/// where it stopped.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MirrorState {
+    /// Next search results page to list.
+    pub next_page: usize,
+    /// Every page was listed once, later crawls only look for new uploads.
+    pub listing_complete: bool,
+}
+
+impl Default for MirrorState {
+    fn default() -> Self {
+        Self {
+            next_page: 1,
+            listing_complete: false,
+        }
+    }
+}
+
+impl Mirror {
+    /// Opens the mirror in `root`, creating the directory if needed.
+    pub fn open(root: &Path) -> Result<Self, CoreError> {
+        fs::create_dir_all(root.join(CHALLENGES_DIR))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    pub fn index(&self) -> Result<ChallengeIndex, CoreError> {
+        ChallengeIndex::open(&self.root.join(INDEX_FILE))
+    }
+
+    pub fn load_state(&self) -> Result<MirrorState, CoreError> {
+        let path = self.root.join(STATE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => read_json(&path, &content),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(MirrorState::default())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save_state(&self, state: &MirrorState) -> Result<(), CoreError> {
+        write_json(&self.root.join(STATE_FILE), state)
+    }
+
+    /// Saves the metadata of a results page and returns how many challenges
+    /// the mirror did not know yet.
+    pub fn record(
+        &self,
+        index: &mut ChallengeIndex,
+        challenges: &[Challenge],
+    ) -> Result<usize, CoreError> {
+        for challenge in challenges {
+            let directory = self.challenge_dir(&challenge.url);
+            fs::create_dir_all(&directory)?;
+            write_json(&directory.join(CHALLENGE_FILE), challenge)?;
+        }
+        index.record(challenges)
+    }
+
+    /// Listed challenges whose page, writeups or archive are still missing.
+    pub fn pending(&self) -> Result<Vec<Challenge>, CoreError> {
+        let mut directories: Vec<PathBuf> = fs::read_dir(self.root.join(CHALLENGES_DIR))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|directory| !directory.join(DETAILS_FILE).exists())
+            .collect();
+        directories.sort();
+
+        directories
+            .iter()
+            .filter(|directory| directory.join(CHALLENGE_FILE).exists())
+            .map(|directory| {
+                let path = directory.join(CHALLENGE_FILE);
+                read_json(&path, &fs::read_to_string(&path)?)
+            })
+            .collect()
+    }
+
+    /// Fetches the page, writeups and archive of a challenge. Files already in
+    /// the mirror are kept, and an interrupted archive download is resumed.
+    pub async fn fetch(
+        &self,
+        challenge: &Challenge,
+        limiter: &RateLimiter,
+    ) -> Result<ChallengeDetails, CoreError> {
+        limiter.wait().await;
+        let details = get_challenge_details(BASE_URL, &challenge.url).await?;
+
+        for writeup in &details.writeup_links {
+            let path = self.writeup_path(&challenge.url, writeup);
+            if path.exists() {
+                continue;
+            }
+            limiter.wait().await;
+            let url = absolute_url(&writeup.url);
+            let body = send(Resource::Writeup, reqwest::Client::new().get(&url))
+                .await?
+                .bytes()
+                .await
+                .map_err(CoreError::request(Resource::Writeup, &url))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomically(&path, &body)?;
+        }
+
+        let archive = self.archive_path(&challenge.url);
+        if !archive.exists() {
+            limiter.wait().await;
+            let sha256 = challenge
+                .download(BASE_URL, &archive, &|_, _| {}, &AtomicBool::new(false))
+                .await?;
+            if let Some(expected) = details.sha256()
+                && !expected.eq_ignore_ascii_case(&sha256)
+            {
+                let _ = fs::remove_file(&archive);
+                return Err(CoreError::HashMismatch {
+                    expected: expected.to_string(),
+                    actual: sha256,
+                });
+            }
+        }
+
+        write_json(
+            &self.challenge_dir(&challenge.url).join(DETAILS_FILE),
+            &details,
+        )?;
+        Ok(details)
+    }
+
+    /// Details of a fully mirrored challenge.
+    pub fn details(&self, url: &str) -> Option<ChallengeDetails> {
+        let content = fs::read_to_string(self.challenge_dir(url).join(DETAILS_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn archive_path(&self, url: &str) -> PathBuf {
+        let directory = self.challenge_dir(url);
+        let id = directory
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        directory.join(format!("{}.zip", id))
+    }
+
+    pub fn writeup_path(&self, url: &str, writeup: &Writeup) -> PathBuf {
+        let file = writeup.url.trim_end_matches('/').rsplit('/').next();
+        self.challenge_dir(url)
+            .join(WRITEUPS_DIR)
+            .join(sanitize_file_name(file.unwrap_or(&writeup.author)))
+    }
+
+    /// Writeup as `get_writeup_text` would return it.
+    pub fn writeup_text(&self, url: &str, writeup: &Writeup) -> Result<String, CoreError> {
+        let path = self.writeup_path(url, writeup);
+        let text = String::from_utf8(fs::read(&path)?).map_err(|_| {
+            CoreError::parse(Resource::Writeup, &path.display().to_string(), "UTF-8 text")
+        })?;
+        if matches!(writeup.get_extension(), "html" | "htm") {
+            Ok(html_to_text(&text))
+        } else {
+            Ok(text)
+        }
+    }
+
+    /// Copies a mirrored writeup where `Writeup::download` would save it.
+    pub fn copy_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        let path = writeup.destination(challenge, directory)?;
+        fs::copy(self.writeup_path(&challenge.url, writeup), &path)?;
+        writeup.settle(challenge, &path)
+    }
+
+    fn challenge_dir(&self, url: &str) -> PathBuf {
+        let id = url.trim_end_matches('/').rsplit('/').next().unwrap_or(url);
+        self.root.join(CHALLENGES_DIR).join(sanitize_file_name(id))
+    }
+}
+
+/// Spaces out requests shared by concurrent tasks.
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot, at least `interval` after the last one.
+    pub async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path, content: &str) -> Result<T, CoreError> {
+    serde_json::from_str(content).map_err(|source| CoreError::InvalidMirror {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), CoreError> {
+    let content =
+        serde_json::to_string_pretty(value).map_err(|source| CoreError::InvalidMirror {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    write_atomically(path, content.as_bytes())
+}
+
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), CoreError> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, content)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_survives_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mirror = Mirror::open(dir.path()).unwrap();
+        assert_eq!(mirror.load_state().unwrap(), MirrorState::default());
+
+        let state = MirrorState {
+            next_page: 12,
+            listing_complete: false,
+        };
+        mirror.save_state(&state).unwrap();
+        assert_eq!(
+            Mirror::open(dir.path()).unwrap().load_state().unwrap(),
+            state
+        );
+    }
+
+    #[test]
+    fn test_pending_skips_mirrored_challenges() {
+        let dir = tempfile::tempdir().unwrap();
+        let mirror = Mirror::open(dir.path()).unwrap();
+        let mut index = mirror.index().unwrap();
+
+        let challenges = [Challenge::sample("done"), Challenge::sample("todo")];
+        assert_eq!(mirror.record(&mut index, &challenges).unwrap(), 2);
+        assert_eq!(mirror.record(&mut index, &challenges).unwrap(), 0);
+
+        let details = ChallengeDetails {
+            description: Some(String::from("Find the key")),
+            writeup_links: vec![Writeup {
+                author: String::from("alice"),
+                url: String::from("/static/writeup/abc.md"),
+            }],
+            ..ChallengeDetails::default()
+        };
+        write_json(
+            &mirror.challenge_dir("/crackme/done").join(DETAILS_FILE),
+            &details,
+        )
+        .unwrap();
+
+        let pending = mirror.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].name, "todo");
+        let loaded = mirror.details("/crackme/done").unwrap();
+        assert_eq!(loaded.description.as_deref(), Some("Find the key"));
+        assert_eq!(
+            mirror.writeup_path("/crackme/done", &loaded.writeup_links[0]),
+            dir.path().join("challenges/done/writeups/abc.md")
+        );
+    }
+}
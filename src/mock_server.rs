@@ -0,0 +1,260 @@
+use std::{
+    io::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Cookie the recorded search page hands out.
+pub const COOKIE_TOKEN: &str = "c00k1e";
+/// Form token of the recorded search page.
+pub const HTML_TOKEN: &str = "f0rm-t0ken";
+/// Challenge of the recorded pages, and the content of its archive.
+pub const CHALLENGE_ID: &str = "6612c5a3a9b3e5f9e1d0c0a1";
+pub const ARCHIVE_FILE: &str = "keygen.rs";
+pub const ARCHIVE_CONTENT: &[u8] = b"fn main() { println!(\"find the key\"); }";
+
+/// A request as the server received it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// What to answer to the requests of a method and path, optionally only to
+/// those whose body contains `when`.
+#[derive(Clone)]
+pub struct Route {
+    method: &'static str,
+    path: String,
+    when: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Route {
+    pub fn new(method: &'static str, path: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            when: None,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn when(mut self, fragment: &str) -> Self {
+        self.when = Some(fragment.to_string());
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn matches(&self, request: &Request) -> bool {
+        self.method == request.method
+            && self.path == request.path
+            && self
+                .when
+                .as_ref()
+                .is_none_or(|fragment| request.body.contains(fragment.as_str()))
+    }
+}
+
+/// A local stand-in for crackmes.one, serving recorded pages over plain HTTP
+/// and keeping every request it got. Unknown routes get a 404.
+pub struct MockServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub async fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes = Arc::new(routes);
+
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (routes, log) = (routes.clone(), log.clone());
+                tokio::spawn(async move { serve(stream, &routes, &log).await });
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    /// The recorded crackmes.one pages: token page, two search result pages,
+    /// the challenge page, a writeup and the archive.
+    pub async fn crackmes() -> Self {
+        let fixture = |name: &str| {
+            std::fs::read(format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+        };
+        let challenge = format!("/crackme/{}", CHALLENGE_ID);
+
+        Self::start(vec![
+            Route::new("GET", "/search", fixture("search_token.html")).header(
+                "Set-Cookie",
+                &format!("gosess={}; Path=/; HttpOnly", COOKIE_TOKEN),
+            ),
+            Route::new("POST", "/search", fixture("search_page_2.html")).when("page=2"),
+            Route::new("POST", "/search", fixture("search_page_1.html")),
+            Route::new("GET", &challenge, fixture("challenge.html")),
+            Route::new("GET", "/static/writeup/w1.md", fixture("writeup.md"))
+                .header("Content-Type", "text/markdown"),
+            Route::new("GET", &format!("/static{}.zip", challenge), archive())
+                .header("Content-Type", "application/zip"),
+        ])
+        .await
+    }
+
+    /// Base URL to hand to the scraper.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A zip holding `ARCHIVE_FILE`, like the challenge archives.
+pub fn archive() -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer
+        .start_file(ARCHIVE_FILE, zip::write::SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(ARCHIVE_CONTENT).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+async fn serve(mut stream: TcpStream, routes: &[Route], log: &Mutex<Vec<Request>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    log.lock().unwrap().push(request.clone());
+
+    let response = match routes.iter().find(|route| route.matches(&request)) {
+        Some(route) => respond(route, &request),
+        None => response_bytes(404, &[], b"not found"),
+    };
+    let _ = stream.write_all(&response).await;
+    let _ = stream.shutdown().await;
+}
+
+// Honours `Range: bytes=<start>-` so that resumed downloads can be tested.
+// A route with its own `Content-Range` always answers with it, like a server
+// ignoring the requested range
+fn respond(route: &Route, request: &Request) -> Vec<u8> {
+    if route
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("content-range"))
+    {
+        return response_bytes(206, &route.headers, &route.body);
+    }
+    let start = request
+        .header("range")
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+
+    match start {
+        Some(start) if start >= route.body.len() => response_bytes(416, &route.headers, b""),
+        Some(start) => {
+            let mut headers = route.headers.clone();
+            headers.push((
+                String::from("Content-Range"),
+                format!(
+                    "bytes {}-{}/{}",
+                    start,
+                    route.body.len() - 1,
+                    route.body.len()
+                ),
+            ));
+            response_bytes(206, &headers, &route.body[start..])
+        }
+        None => response_bytes(200, &route.headers, &route.body),
+    }
+}
+
+fn response_bytes(status: u16, headers: &[(String, String)], body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+
+    let mut bytes = response.into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = String::from_utf8_lossy(&buffer[header_end..]).into_owned();
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,187 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::{challenge::Challenge, cli::OutputFormat, search::absolute_url};
+
+/// Flat view of a challenge used by every machine-readable format.
+#[derive(Serialize)]
+pub struct ChallengeRecord<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub author: &'a str,
+    pub language: String,
+    pub arch: String,
+    pub platform: String,
+    pub difficulty: f32,
+    pub quality: f32,
+    pub url: String,
+}
+
+impl<'a> From<&'a Challenge> for ChallengeRecord<'a> {
+    fn from(challenge: &'a Challenge) -> Self {
+        Self {
+            id: challenge.id(),
+            name: &challenge.name,
+            author: &challenge.author,
+            language: challenge.language.to_string(),
+            arch: challenge.arch.to_string(),
+            platform: challenge.platform.to_string(),
+            difficulty: challenge.difficulty,
+            quality: challenge.quality,
+            url: absolute_url(&challenge.url),
+        }
+    }
+}
+
+pub fn write_challenges(
+    out: &mut impl Write,
+    challenges: &[Challenge],
+    format: OutputFormat,
+) -> std::io::Result<()> {
+    let records: Vec<ChallengeRecord> = challenges.iter().map(ChallengeRecord::from).collect();
+
+    match format {
+        OutputFormat::Text => write_text(out, &records),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &records)?;
+            writeln!(out)
+        }
+        OutputFormat::Jsonl => {
+            for record in &records {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        OutputFormat::Csv => write_delimited(out, &records, b','),
+        OutputFormat::Tsv => write_delimited(out, &records, b'\t'),
+        OutputFormat::Markdown => write_markdown(out, &records),
+    }
+}
+
+fn write_text(out: &mut impl Write, records: &[ChallengeRecord]) -> std::io::Result<()> {
+    for record in records {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{:.1}\t{:.1}\t{}\t{}\t{}",
+            record.id,
+            record.name,
+            record.author,
+            record.difficulty,
+            record.quality,
+            record.language,
+            record.arch,
+            record.platform,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_delimited(
+    out: &mut impl Write,
+    records: &[ChallengeRecord],
+    delimiter: u8,
+) -> std::io::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(out);
+    for record in records {
+        writer.serialize(record)?;
+    }
+    writer.flush()
+}
+
+fn write_markdown(out: &mut impl Write, records: &[ChallengeRecord]) -> std::io::Result<()> {
+    // Pipes would otherwise split a cell in two
+    let cell = |s: &str| s.replace('|', "\\|");
+
+    writeln!(
+        out,
+        "| ID | Name | Author | Language | Arch | Platform | Difficulty | Quality |"
+    )?;
+    writeln!(out, "|---|---|---|---|---|---|---:|---:|")?;
+    for record in records {
+        writeln!(
+            out,
+            "| [{}]({}) | {} | {} | {} | {} | {} | {:.1} | {:.1} |",
+            record.id,
+            record.url,
+            cell(record.name),
+            cell(record.author),
+            cell(&record.language),
+            cell(&record.arch),
+            cell(&record.platform),
+            record.difficulty,
+            record.quality,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Language, Platform};
+
+    fn sample() -> Vec<Challenge> {
+        vec![Challenge {
+            name: String::from("easy, keygen"),
+            quality: 4.5,
+            ..Challenge::sample("abc123")
+        }]
+    }
+
+    fn render(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        write_challenges(&mut out, &sample(), format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_jsonl_keeps_site_labels_and_id() {
+        let line = render(OutputFormat::Jsonl);
+        let value: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(value["id"], "abc123");
+        assert_eq!(value["language"], "C/C++");
+        assert_eq!(value["arch"], "x86-64");
+        assert_eq!(value["platform"], "Unix/linux etc.");
+        assert_eq!(value["url"], "https://crackmes.one/crackme/abc123");
+    }
+
+    #[test]
+    fn test_csv_quotes_fields_and_has_header() {
+        let csv = render(OutputFormat::Csv);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("id,name,author,language,arch,platform,difficulty,quality,url")
+        );
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .starts_with("abc123,\"easy, keygen\",bob,C/C++")
+        );
+    }
+
+    #[test]
+    fn test_challenge_round_trips_through_serde() {
+        let json = serde_json::to_string(&sample()[0]).unwrap();
+        let challenge: Challenge = serde_json::from_str(&json).unwrap();
+        assert!(matches!(challenge.language, Language::Ccpp));
+        assert!(matches!(challenge.platform, Platform::Unix));
+    }
+
+    #[test]
+    fn test_unknown_site_labels_are_kept() {
+        let mut challenge = sample().remove(0);
+        challenge.platform = Platform::from("Windows 10");
+        let json = serde_json::to_string(&challenge).unwrap();
+        assert!(json.contains(r#""platform":"Windows 10""#));
+
+        let challenge: Challenge = serde_json::from_str(&json).unwrap();
+        assert!(matches!(&challenge.platform, Platform::Other(label) if label == "Windows 10"));
+        assert_eq!(ChallengeRecord::from(&challenge).platform, "Windows 10");
+    }
+}
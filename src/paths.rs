@@ -0,0 +1,235 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::errors::CoreError;
+
+// In bytes, keeps generated names readable and well below the 255 bytes most
+// file systems allow, leaving room for a discriminator and an extension
+const MAX_FILE_NAME_LEN: usize = 100;
+
+/// Turns an arbitrary site-provided string into a single, harmless path
+/// component.
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut sanitized = String::new();
+    for c in name.chars() {
+        let c = match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        };
+        if sanitized.len() + c.len_utf8() > MAX_FILE_NAME_LEN {
+            break;
+        }
+        sanitized.push(c);
+    }
+
+    // Leading dots would make the file hidden, or `..`
+    let sanitized = sanitized.trim().trim_start_matches('.').trim_end();
+    if sanitized.is_empty() {
+        String::from("challenge")
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Picks `<stem><extension>` in `root`, falling back to
+/// `<stem>-<discriminator><extension>` when the former is taken by something
+/// `is_ours` does not recognise.
+pub fn unique_path(
+    root: &Path,
+    stem: &str,
+    discriminator: &str,
+    extension: &str,
+    is_ours: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    let preferred = root.join(format!("{}{}", sanitize_file_name(stem), extension));
+    if !preferred.exists() || is_ours(&preferred) {
+        return preferred;
+    }
+
+    root.join(format!(
+        "{}-{}{}",
+        sanitize_file_name(stem),
+        sanitize_file_name(discriminator),
+        extension
+    ))
+}
+
+/// Where a file of the challenge `id` is first written:
+/// `<stem>-<id><extension>`, a name no namesake challenge can share.
+/// [`settle_path`] then gives it the plain `<stem><extension>` when it can.
+pub fn discriminated_path(root: &Path, stem: &str, id: &str, extension: &str) -> PathBuf {
+    root.join(format!(
+        "{}-{}{}",
+        sanitize_file_name(stem),
+        sanitize_file_name(id),
+        extension
+    ))
+}
+
+/// Moves `written` to `<stem><extension>` next to it when that name is free
+/// or already holds the very same content, and returns where the file ended
+/// up. A name taken by anything else, a namesake challenge or a file of the
+/// user, is left alone and `written` stays where it is.
+pub fn settle_path(written: &Path, stem: &str, extension: &str) -> Result<PathBuf, CoreError> {
+    let preferred = written.with_file_name(format!("{}{}", sanitize_file_name(stem), extension));
+    // Linking fails on an existing name, so concurrent downloads, even from
+    // another process, cannot both claim it
+    match fs::hard_link(written, &preferred) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            let is_file = fs::symlink_metadata(&preferred).is_ok_and(|m| m.is_file());
+            if !is_file || !same_content(written, &preferred)? {
+                return Ok(written.to_path_buf());
+            }
+        }
+        // No hard links on this file system
+        Err(_) if !preferred.exists() => {
+            fs::rename(written, &preferred)?;
+            return Ok(preferred);
+        }
+        Err(_) => return Ok(written.to_path_buf()),
+    }
+    fs::remove_file(written)?;
+    Ok(preferred)
+}
+
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let (mut a, mut b) = (
+        BufReader::new(File::open(a)?),
+        BufReader::new(File::open(b)?),
+    );
+    loop {
+        let (left, right) = (a.fill_buf()?, b.fill_buf()?);
+        if left.is_empty() || right.is_empty() {
+            return Ok(left.is_empty() && right.is_empty());
+        }
+        let length = left.len().min(right.len());
+        if left[..length] != right[..length] {
+            return Ok(false);
+        }
+        a.consume(length);
+        b.consume(length);
+    }
+}
+
+/// Makes sure `path` resolves inside `root`, following any symlink on the way.
+pub fn ensure_within(root: &Path, path: &Path) -> Result<(), CoreError> {
+    let unsafe_path = || CoreError::UnsafePath(path.to_path_buf());
+    let relative = path.strip_prefix(root).map_err(|_| unsafe_path())?;
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(unsafe_path());
+    }
+
+    let root = fs::canonicalize(root)?;
+    // Only the existing part of the path can be resolved
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(unsafe_path)?;
+    let resolved = fs::canonicalize(existing)?;
+
+    if resolved.starts_with(&root) {
+        Ok(())
+    } else {
+        Err(unsafe_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        let cases = [
+            ("easy_keygen", "easy_keygen"),
+            ("../../etc/passwd", "_.._etc_passwd"),
+            ("..", "challenge"),
+            ("a/b\\c", "a_b_c"),
+            ("bell\x07name\n", "bell_name_"),
+            ("  .hidden ", "hidden"),
+            ("", "challenge"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(sanitize_file_name(name), expected, "sanitizing {:?}", name);
+        }
+        assert_eq!(
+            sanitize_file_name(&"a".repeat(500)).len(),
+            MAX_FILE_NAME_LEN
+        );
+    }
+
+    #[test]
+    fn test_sanitize_file_name_counts_bytes() {
+        // Three bytes per character, four per emoji
+        for name in ["鍵".repeat(100), "🔑".repeat(100)] {
+            let sanitized = sanitize_file_name(&name);
+            assert!(sanitized.len() <= MAX_FILE_NAME_LEN, "{}", sanitized.len());
+            assert!(sanitized.len() > MAX_FILE_NAME_LEN - 4);
+            assert!(name.starts_with(&sanitized));
+        }
+    }
+
+    #[test]
+    fn test_unique_path_avoids_foreign_files() {
+        let root = tempfile::tempdir().unwrap();
+        let free = unique_path(root.path(), "crackme", "abc", ".zip", |_| false);
+        assert_eq!(free, root.path().join("crackme.zip"));
+
+        fs::write(&free, b"someone else").unwrap();
+        let taken = unique_path(root.path(), "crackme", "abc", ".zip", |_| false);
+        assert_eq!(taken, root.path().join("crackme-abc.zip"));
+
+        let ours = unique_path(root.path(), "crackme", "abc", ".zip", |_| true);
+        assert_eq!(ours, free);
+    }
+
+    #[test]
+    fn test_settle_path_keeps_namesakes_and_user_files() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |id: &str, content: &str| {
+            let path = discriminated_path(root.path(), "crackme", id, ".zip");
+            fs::write(&path, content).unwrap();
+            settle_path(&path, "crackme", ".zip").unwrap()
+        };
+
+        let plain = root.path().join("crackme.zip");
+        assert_eq!(write("abc", "abc"), plain);
+        // Downloading the same archive again reuses the plain name
+        assert_eq!(write("abc", "abc"), plain);
+        assert!(!root.path().join("crackme-abc.zip").exists());
+
+        let namesake = write("def", "def");
+        assert_eq!(namesake, root.path().join("crackme-def.zip"));
+        assert_eq!(fs::read_to_string(&plain).unwrap(), "abc");
+
+        // Files cracked did not write are never taken over
+        fs::write(root.path().join("mine.zip"), b"user").unwrap();
+        let path = discriminated_path(root.path(), "mine", "abc", ".zip");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(settle_path(&path, "mine", ".zip").unwrap(), path);
+        assert_eq!(fs::read(root.path().join("mine.zip")).unwrap(), b"user");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ensure_within_rejects_symlink_escape() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+
+        assert!(ensure_within(root.path(), &root.path().join("file.zip")).is_ok());
+        assert!(ensure_within(root.path(), &root.path().join("link/file.zip")).is_err());
+        assert!(ensure_within(root.path(), &root.path().join("../file.zip")).is_err());
+    }
+}
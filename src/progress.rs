@@ -0,0 +1,291 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{challenge::Challenge, errors::CoreError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStatus {
+    #[default]
+    New,
+    Downloaded,
+    InProgress,
+    Solved,
+    GaveUp,
+}
+
+impl ProgressStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            ProgressStatus::New => "new",
+            ProgressStatus::Downloaded => "downloaded",
+            ProgressStatus::InProgress => "in progress",
+            ProgressStatus::Solved => "solved",
+            ProgressStatus::GaveUp => "gave up",
+        }
+    }
+
+    /// One character marker for the challenge list.
+    pub fn marker(self) -> &'static str {
+        match self {
+            ProgressStatus::New => " ",
+            ProgressStatus::Downloaded => "↓",
+            ProgressStatus::InProgress => "~",
+            ProgressStatus::Solved => "✓",
+            ProgressStatus::GaveUp => "✗",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub status: ProgressStatus,
+    /// Unix timestamp, in seconds.
+    pub at: u64,
+}
+
+/// What we know about our attempts at one challenge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeProgress {
+    /// The challenge as it was listed when its status last changed.
+    pub challenge: Challenge,
+    pub status: ProgressStatus,
+    /// Every status change, oldest first.
+    pub history: Vec<StatusChange>,
+    /// Seconds spent in progress, not counting the current session.
+    pub time_spent: u64,
+    /// When the current in progress session started.
+    pub started_at: Option<u64>,
+}
+
+impl ChallengeProgress {
+    /// Seconds spent in progress, including the running session.
+    pub fn time_spent_at(&self, now: u64) -> u64 {
+        self.time_spent
+            + self
+                .started_at
+                .map(|started| now.saturating_sub(started))
+                .unwrap_or(0)
+    }
+
+    // When the status last changed, 0 if it never did
+    fn changed_at(&self) -> u64 {
+        self.history.last().map(|change| change.at).unwrap_or(0)
+    }
+}
+
+/// Status of every challenge we interacted with, keyed by challenge URL and
+/// saved as JSON in `<data dir>/cracked/progress.json`.
+#[derive(Debug, Default)]
+pub struct ProgressStore {
+    path: Option<PathBuf>,
+    entries: BTreeMap<String, ChallengeProgress>,
+}
+
+impl ProgressStore {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("cracked").join("progress.json"))
+    }
+
+    /// Loads the store from its default location, starting empty the first
+    /// time.
+    pub fn load() -> Result<Self, CoreError> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, CoreError> {
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            entries: read_entries(path)?,
+        })
+    }
+
+    /// Writes the store back, through a temporary file so that a crash never
+    /// leaves it half written.
+    ///
+    /// Another process, say a download next to the TUI, may have saved in the
+    /// meantime: its entries are kept unless ours changed later.
+    pub fn save(&self) -> Result<(), CoreError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut entries = read_entries(path)?;
+        for (url, entry) in &self.entries {
+            if entries
+                .get(url)
+                .is_none_or(|saved| saved.changed_at() <= entry.changed_at())
+            {
+                entries.insert(url.clone(), entry.clone());
+            }
+        }
+        let content = serde_json::to_string_pretty(&entries).map_err(CoreError::InvalidProgress)?;
+        let temporary = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, url: &str) -> Option<&ChallengeProgress> {
+        self.entries.get(url)
+    }
+
+    pub fn status(&self, url: &str) -> ProgressStatus {
+        self.get(url).map(|entry| entry.status).unwrap_or_default()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ChallengeProgress> {
+        self.entries.values()
+    }
+
+    /// Records a status change at `now`, closing the running in progress
+    /// session if there is one.
+    pub fn set_status(&mut self, challenge: &Challenge, status: ProgressStatus, now: u64) {
+        let entry = self
+            .entries
+            .entry(challenge.url.clone())
+            .or_insert_with(|| ChallengeProgress {
+                challenge: challenge.clone(),
+                status: ProgressStatus::New,
+                history: Vec::new(),
+                time_spent: 0,
+                started_at: None,
+            });
+        if entry.status == status {
+            return;
+        }
+
+        entry.challenge = challenge.clone();
+        if let Some(started) = entry.started_at.take() {
+            entry.time_spent += now.saturating_sub(started);
+        }
+        if status == ProgressStatus::InProgress {
+            entry.started_at = Some(now);
+        }
+        entry.status = status;
+        entry.history.push(StatusChange { status, at: now });
+    }
+
+    /// Marks a challenge as downloaded, unless we already went further.
+    pub fn mark_downloaded(&mut self, challenge: &Challenge, now: u64) {
+        if self.status(&challenge.url) == ProgressStatus::New {
+            self.set_status(challenge, ProgressStatus::Downloaded, now);
+        }
+    }
+}
+
+fn read_entries(path: &Path) -> Result<BTreeMap<String, ChallengeProgress>, CoreError> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(CoreError::InvalidProgress),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Current Unix timestamp, in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats a number of seconds as `1h 05m`, or `4m` under an hour.
+pub fn format_duration(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            name: String::from("easy_keygen"),
+            ..Challenge::sample("abc123")
+        }
+    }
+
+    #[test]
+    fn test_time_spent_accumulates_over_sessions() {
+        let mut store = ProgressStore::default();
+        let challenge = challenge();
+
+        store.set_status(&challenge, ProgressStatus::InProgress, 100);
+        store.set_status(&challenge, ProgressStatus::Downloaded, 160);
+        store.set_status(&challenge, ProgressStatus::InProgress, 1000);
+        assert_eq!(store.get(&challenge.url).unwrap().time_spent_at(1030), 90);
+
+        store.set_status(&challenge, ProgressStatus::Solved, 1100);
+        let entry = store.get(&challenge.url).unwrap();
+        assert_eq!(entry.time_spent_at(5000), 160);
+        assert_eq!(entry.history.len(), 4);
+        assert_eq!(entry.history.last().unwrap().at, 1100);
+    }
+
+    #[test]
+    fn test_mark_downloaded_keeps_later_statuses() {
+        let mut store = ProgressStore::default();
+        let challenge = challenge();
+
+        store.mark_downloaded(&challenge, 10);
+        assert_eq!(store.status(&challenge.url), ProgressStatus::Downloaded);
+
+        store.set_status(&challenge, ProgressStatus::Solved, 20);
+        store.mark_downloaded(&challenge, 30);
+        assert_eq!(store.status(&challenge.url), ProgressStatus::Solved);
+    }
+
+    #[test]
+    fn test_store_round_trips_through_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cracked").join("progress.json");
+
+        let mut store = ProgressStore::load_from(&path).unwrap();
+        store.set_status(&challenge(), ProgressStatus::GaveUp, 42);
+        store.save().unwrap();
+
+        let store = ProgressStore::load_from(&path).unwrap();
+        assert_eq!(store.status("/crackme/abc123"), ProgressStatus::GaveUp);
+        assert_eq!(format_duration(3900), "1h 05m");
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_each_others_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+        let mut tui = ProgressStore::load_from(&path).unwrap();
+        let mut download = ProgressStore::load_from(&path).unwrap();
+        let other = Challenge::sample("other");
+
+        tui.set_status(&challenge(), ProgressStatus::InProgress, 10);
+        download.mark_downloaded(&other, 20);
+        download.set_status(&challenge(), ProgressStatus::Downloaded, 5);
+        download.save().unwrap();
+        tui.save().unwrap();
+
+        let store = ProgressStore::load_from(&path).unwrap();
+        assert_eq!(store.status(&other.url), ProgressStatus::Downloaded);
+        assert_eq!(
+            store.status("/crackme/abc123"),
+            ProgressStatus::InProgress,
+            "the later change wins"
+        );
+    }
+}
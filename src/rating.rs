@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use crate::{
+    challenge::Challenge,
+    progress::{ProgressStatus, ProgressStore},
+};
+
+// Elo points per point of site difficulty, difficulty 1 rating 1000
+const POINTS_PER_DIFFICULTY: f32 = 200.0;
+const BASE_RATING: f32 = 1000.0;
+// Where a newcomer starts, between the two easiest difficulties
+const INITIAL_USER_DIFFICULTY: f32 = 1.5;
+// K factor of a new rating, shrinking as outcomes come in down to the minimum
+const INITIAL_K: f32 = 64.0;
+const MIN_K: f32 = 16.0;
+// Score of a solve that took far longer than usual, a quick one scores 1
+const SLOW_SOLVE_SCORE: f32 = 0.6;
+// Typical solve time until we have solves with a recorded time
+const DEFAULT_SOLVE_TIME: u64 = 60 * 60;
+
+/// Elo rating with a Glicko flavour: the fewer outcomes it is based on, the
+/// more the next one moves it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub value: f32,
+    pub outcomes: u32,
+}
+
+impl Rating {
+    fn new(value: f32) -> Self {
+        Self { value, outcomes: 0 }
+    }
+
+    fn k_factor(self) -> f32 {
+        (INITIAL_K / (1.0 + self.outcomes as f32).sqrt()).max(MIN_K)
+    }
+}
+
+/// Ratings of the user and of the challenges they finished, replayed from the
+/// progress store.
+#[derive(Debug)]
+pub struct Ratings {
+    pub user: Rating,
+    challenges: HashMap<String, Rating>,
+}
+
+impl Ratings {
+    /// Replays every solve and give up in the order they happened, going
+    /// through the status history so that a challenge given up and solved
+    /// later counts as both. A solve is timed with the in progress sessions
+    /// that led to it.
+    pub fn compute(store: &ProgressStore) -> Self {
+        let mut outcomes: Vec<(u64, &Challenge, Option<u64>)> = Vec::new();
+        for entry in store.entries() {
+            let mut time_spent = 0;
+            let mut started = None;
+            for change in &entry.history {
+                if let Some(started) = started.take() {
+                    time_spent += change.at.saturating_sub(started);
+                }
+                match change.status {
+                    ProgressStatus::InProgress => started = Some(change.at),
+                    ProgressStatus::Solved => {
+                        outcomes.push((change.at, &entry.challenge, Some(time_spent)))
+                    }
+                    ProgressStatus::GaveUp => outcomes.push((change.at, &entry.challenge, None)),
+                    _ => {}
+                }
+            }
+        }
+        outcomes.sort_by_key(|(at, _, _)| *at);
+        let typical_time = typical_solve_time(&outcomes);
+
+        let mut ratings = Self {
+            user: Rating::new(difficulty_to_rating(INITIAL_USER_DIFFICULTY)),
+            challenges: HashMap::new(),
+        };
+        for (_, challenge, solve_time) in outcomes {
+            let score = match solve_time {
+                Some(time) => solve_score(time, typical_time),
+                None => 0.0,
+            };
+            ratings.record(challenge, score);
+        }
+        ratings
+    }
+
+    /// Rating of a challenge, its site difficulty until we finished it.
+    pub fn challenge(&self, challenge: &Challenge) -> Rating {
+        self.challenges
+            .get(&challenge.url)
+            .copied()
+            .unwrap_or_else(|| Rating::new(difficulty_to_rating(challenge.difficulty)))
+    }
+
+    /// Site difficulty the challenge would have for a user of the initial
+    /// rating: challenges feel easier as the user gets better.
+    pub fn expected_difficulty(&self, challenge: &Challenge) -> f32 {
+        let progress = self.user.value - difficulty_to_rating(INITIAL_USER_DIFFICULTY);
+        rating_to_difficulty(self.challenge(challenge).value - progress)
+    }
+
+    // `score` is 1 for a quick solve and 0 for a give up
+    fn record(&mut self, challenge: &Challenge, score: f32) {
+        let mut challenge_rating = self.challenge(challenge);
+        let expected = expected_score(self.user.value, challenge_rating.value);
+
+        self.user.value += self.user.k_factor() * (score - expected);
+        self.user.outcomes += 1;
+        challenge_rating.value -= challenge_rating.k_factor() * (score - expected);
+        challenge_rating.outcomes += 1;
+        self.challenges
+            .insert(challenge.url.clone(), challenge_rating);
+    }
+}
+
+fn difficulty_to_rating(difficulty: f32) -> f32 {
+    BASE_RATING + (difficulty - 1.0) * POINTS_PER_DIFFICULTY
+}
+
+fn rating_to_difficulty(rating: f32) -> f32 {
+    (1.0 + (rating - BASE_RATING) / POINTS_PER_DIFFICULTY).clamp(1.0, 6.0)
+}
+
+/// Probability that a user of rating `user` solves a challenge of rating
+/// `challenge`.
+fn expected_score(user: f32, challenge: f32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((challenge - user) / 400.0))
+}
+
+// Solving in the usual time or faster counts as a full win, slower solves
+// score less down to `SLOW_SOLVE_SCORE`
+fn solve_score(time: u64, typical: u64) -> f32 {
+    if time == 0 || time <= typical {
+        return 1.0;
+    }
+    SLOW_SOLVE_SCORE + (1.0 - SLOW_SOLVE_SCORE) * typical as f32 / time as f32
+}
+
+// Median of the recorded solve times
+fn typical_solve_time(outcomes: &[(u64, &Challenge, Option<u64>)]) -> u64 {
+    let mut times: Vec<u64> = outcomes
+        .iter()
+        .filter_map(|(_, _, time)| *time)
+        .filter(|&time| time > 0)
+        .collect();
+    if times.is_empty() {
+        return DEFAULT_SOLVE_TIME;
+    }
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(id: &str, difficulty: f32) -> Challenge {
+        Challenge {
+            difficulty,
+            ..Challenge::sample(id)
+        }
+    }
+
+    #[test]
+    fn test_solves_raise_the_user_and_lower_the_challenge() {
+        let mut store = ProgressStore::default();
+        let solved = challenge("solved", 3.0);
+        let other = challenge("other", 3.0);
+        store.set_status(&solved, ProgressStatus::Solved, 10);
+
+        let ratings = Ratings::compute(&store);
+        assert!(ratings.user.value > difficulty_to_rating(INITIAL_USER_DIFFICULTY));
+        assert!(ratings.challenge(&solved).value < difficulty_to_rating(3.0));
+        assert!(ratings.expected_difficulty(&other) < 3.0);
+    }
+
+    #[test]
+    fn test_give_ups_make_challenges_feel_harder() {
+        let mut store = ProgressStore::default();
+        store.set_status(&challenge("hard", 2.0), ProgressStatus::GaveUp, 10);
+
+        let ratings = Ratings::compute(&store);
+        assert_eq!(ratings.user.outcomes, 1);
+        assert!(ratings.expected_difficulty(&challenge("next", 2.0)) > 2.0);
+    }
+
+    #[test]
+    fn test_every_outcome_of_a_challenge_counts() {
+        let mut store = ProgressStore::default();
+        let retried = challenge("retried", 2.0);
+        store.set_status(&retried, ProgressStatus::InProgress, 0);
+        store.set_status(&retried, ProgressStatus::GaveUp, 600);
+        store.set_status(&retried, ProgressStatus::InProgress, 1000);
+        store.set_status(&retried, ProgressStatus::Solved, 1600);
+
+        let ratings = Ratings::compute(&store);
+        assert_eq!(ratings.user.outcomes, 2);
+        assert_eq!(ratings.challenge(&retried).outcomes, 2);
+    }
+
+    #[test]
+    fn test_slow_solves_score_less() {
+        assert_eq!(solve_score(600, 1200), 1.0);
+        assert_eq!(solve_score(2400, 1200), 0.8);
+        assert!(solve_score(100_000, 1200) > SLOW_SOLVE_SCORE);
+        assert_eq!(rating_to_difficulty(difficulty_to_rating(4.5)), 4.5);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use crate::{
+    challenge::Challenge,
+    progress::{ProgressStatus, ProgressStore},
+};
+
+// How far above the comfort level recommendations aim
+const STRETCH: f32 = 0.5;
+// Comfort level of someone who has not solved anything yet
+const BEGINNER_LEVEL: f32 = 1.0;
+// Solves averaged to find the comfort level, most recent first
+const RECENT_SOLVES: usize = 10;
+// Spread of the difficulty fit, a challenge this far from the target scores
+// about a third of one right on it
+const DIFFICULTY_SPREAD: f32 = 0.75;
+
+pub struct Recommendation {
+    /// Index of the challenge in the ranked slice.
+    pub index: usize,
+    pub score: f32,
+}
+
+/// Scores challenges against a solve history.
+pub struct Recommender {
+    /// Average difficulty of the recent solves.
+    pub comfort_level: f32,
+    /// Difficulty recommendations aim for.
+    pub target: f32,
+    solves_per_arch: HashMap<String, u64>,
+    total_solves: u64,
+}
+
+impl Recommender {
+    pub fn new(store: &ProgressStore) -> Self {
+        let mut solved: Vec<(u64, &Challenge)> = store
+            .entries()
+            .filter(|entry| entry.status == ProgressStatus::Solved)
+            .map(|entry| {
+                let at = entry.history.last().map(|change| change.at).unwrap_or(0);
+                (at, &entry.challenge)
+            })
+            .collect();
+        solved.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let recent: Vec<f32> = solved
+            .iter()
+            .take(RECENT_SOLVES)
+            .map(|(_, challenge)| challenge.difficulty)
+            .collect();
+        let comfort_level = if recent.is_empty() {
+            BEGINNER_LEVEL
+        } else {
+            recent.iter().sum::<f32>() / recent.len() as f32
+        };
+
+        let mut solves_per_arch = HashMap::new();
+        for (_, challenge) in &solved {
+            *solves_per_arch
+                .entry(challenge.arch.to_string())
+                .or_default() += 1;
+        }
+
+        Self {
+            comfort_level,
+            target: comfort_level + STRETCH,
+            solves_per_arch,
+            total_solves: solved.len() as u64,
+        }
+    }
+
+    /// Integer difficulty range around the target, to narrow down searches.
+    pub fn difficulty_range(&self) -> [i32; 2] {
+        let low = (self.target - DIFFICULTY_SPREAD).floor().max(1.0) as i32;
+        let high = (self.target + DIFFICULTY_SPREAD).ceil().min(6.0) as i32;
+        [low, high.max(low)]
+    }
+
+    /// Between 0 and 1: how close the challenge is to the target difficulty,
+    /// weighted towards quality and towards the least practised archs.
+    pub fn score(&self, challenge: &Challenge) -> f32 {
+        let distance = (challenge.difficulty - self.target) / DIFFICULTY_SPREAD;
+        let difficulty_fit = (-distance * distance).exp();
+        let quality = (challenge.quality / 6.0).clamp(0.0, 1.0);
+        let arch_share = match self.total_solves {
+            0 => 0.0,
+            total => {
+                let solves = self
+                    .solves_per_arch
+                    .get(&challenge.arch.to_string())
+                    .copied()
+                    .unwrap_or(0);
+                solves as f32 / total as f32
+            }
+        };
+
+        // Quality weighs more than arch variety, a poor challenge on a new
+        // arch is still a poor challenge
+        difficulty_fit * (0.25 + 0.75 * quality) * (1.0 - 0.2 * arch_share)
+    }
+
+    /// The best `count` challenges we did not start, best first.
+    pub fn rank(
+        &self,
+        challenges: &[Challenge],
+        store: &ProgressStore,
+        count: usize,
+    ) -> Vec<Recommendation> {
+        let mut recommendations: Vec<Recommendation> = challenges
+            .iter()
+            .enumerate()
+            .filter(|(_, challenge)| {
+                matches!(
+                    store.status(&challenge.url),
+                    ProgressStatus::New | ProgressStatus::Downloaded
+                )
+            })
+            .map(|(index, challenge)| Recommendation {
+                index,
+                score: self.score(challenge),
+            })
+            .collect();
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        recommendations.truncate(count);
+        recommendations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Arch;
+
+    fn challenge(id: &str, difficulty: f32, quality: f32, arch: Arch) -> Challenge {
+        Challenge {
+            arch,
+            difficulty,
+            quality,
+            ..Challenge::sample(id)
+        }
+    }
+
+    fn history() -> ProgressStore {
+        let mut store = ProgressStore::default();
+        for (at, id) in ["a", "b", "c"].into_iter().enumerate() {
+            let solved = challenge(id, 2.0, 4.0, Arch::X8664);
+            store.set_status(&solved, ProgressStatus::Solved, at as u64);
+        }
+        store
+    }
+
+    #[test]
+    fn test_targets_slightly_above_comfort_level() {
+        let recommender = Recommender::new(&history());
+        assert_eq!(recommender.comfort_level, 2.0);
+        assert_eq!(recommender.target, 2.5);
+        assert_eq!(recommender.difficulty_range(), [1, 4]);
+
+        let beginner = Recommender::new(&ProgressStore::default());
+        assert_eq!(beginner.target, BEGINNER_LEVEL + STRETCH);
+    }
+
+    #[test]
+    fn test_rank_prefers_fit_quality_and_new_archs() {
+        let store = history();
+        let challenges = vec![
+            challenge("too_hard", 5.5, 5.0, Arch::Arm),
+            challenge("practised_arch", 2.5, 4.0, Arch::X8664),
+            challenge("new_arch", 2.5, 4.0, Arch::Arm),
+            challenge("poor", 2.5, 1.0, Arch::Arm),
+            challenge("a", 2.5, 6.0, Arch::Arm),
+        ];
+
+        let ranked = Recommender::new(&store).rank(&challenges, &store, 3);
+        let names: Vec<&str> = ranked
+            .iter()
+            .map(|recommendation| challenges[recommendation.index].name.as_str())
+            .collect();
+        assert_eq!(names, vec!["new_arch", "practised_arch", "poor"]);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    challenge::Challenge,
+    progress::{ProgressStatus, ProgressStore},
+};
+
+/// Result pages fetched before picking, so that older challenges get a chance
+/// and not only the latest uploads.
+pub const RANDOM_PAGES: usize = 5;
+
+/// Picks a challenge we never touched, uniformly or, with `weighted`, in
+/// proportion to its quality. Returns its index in `challenges`.
+pub fn pick(
+    challenges: &[&Challenge],
+    store: &ProgressStore,
+    weighted: bool,
+    rng: &mut fastrand::Rng,
+) -> Option<usize> {
+    let candidates: Vec<usize> = challenges
+        .iter()
+        .enumerate()
+        .filter(|(_, challenge)| store.status(&challenge.url) == ProgressStatus::New)
+        .map(|(index, _)| index)
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    if !weighted {
+        return Some(candidates[rng.usize(..candidates.len())]);
+    }
+
+    // Unrated challenges keep a small chance
+    let weight = |index: usize| challenges[index].quality.max(0.1);
+    let total: f32 = candidates.iter().map(|&index| weight(index)).sum();
+    let mut target = rng.f32() * total;
+    for &index in &candidates {
+        target -= weight(index);
+        if target <= 0.0 {
+            return Some(index);
+        }
+    }
+    candidates.last().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(id: &str, quality: f32) -> Challenge {
+        Challenge {
+            quality,
+            ..Challenge::sample(id)
+        }
+    }
+
+    #[test]
+    fn test_pick_skips_solved_and_downloaded() {
+        let challenges = [
+            challenge("solved", 5.0),
+            challenge("downloaded", 5.0),
+            challenge("new", 1.0),
+        ];
+        let references: Vec<&Challenge> = challenges.iter().collect();
+        let mut store = ProgressStore::default();
+        store.set_status(&challenges[0], ProgressStatus::Solved, 1);
+        store.mark_downloaded(&challenges[1], 1);
+
+        let mut rng = fastrand::Rng::with_seed(7);
+        for weighted in [false, true] {
+            assert_eq!(pick(&references, &store, weighted, &mut rng), Some(2));
+        }
+
+        store.mark_downloaded(&challenges[2], 2);
+        assert_eq!(pick(&references, &store, false, &mut rng), None);
+    }
+
+    #[test]
+    fn test_weighted_pick_favours_quality() {
+        let challenges = [challenge("poor", 0.5), challenge("mint", 5.0)];
+        let references: Vec<&Challenge> = challenges.iter().collect();
+        let store = ProgressStore::default();
+        let mut rng = fastrand::Rng::with_seed(42);
+
+        let mint = (0..1000)
+            .filter(|_| pick(&references, &store, true, &mut rng) == Some(1))
+            .count();
+        assert!(mint > 850, "picked the best challenge {} times", mint);
+    }
+}
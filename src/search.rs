@@ -1,6 +1,14 @@
-use crate::{challenge::Challenge, errors::CoreError};
-use scraper::{Html, Selector};
+use crate::{
+    challenge::{Challenge, ChallengeComment, ChallengeDetails, UNRATED, Writeup},
+    errors::{CoreError, Resource},
+    view::parse_date,
+};
+use reqwest::{Client, RequestBuilder, Response};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::mem::discriminant;
 
+#[derive(Clone)]
 pub struct SearchParams {
     pub name: Option<String>,
     pub author: Option<String>,
@@ -9,58 +17,75 @@ pub struct SearchParams {
     pub language: Option<Language>,
     pub arch: Option<Arch>,
     pub platform: Option<Platform>,
+    /// Full-text query, only understood by the local index.
+    pub query: Option<String>,
     pub token: String,
 }
 
-impl std::fmt::Display for SearchParams {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = self.name.as_deref().unwrap_or("");
-        let author = self.author.as_deref().unwrap_or("");
-
-        let (difficulty_min, difficulty_max) = match &self.difficulty_range {
-            Some(range) => (range[0].to_string(), range[1].to_string()),
-            None => (String::new(), String::new()),
+impl SearchParams {
+    /// Mirrors the site search, for challenges we already have at hand.
+    /// Unrated challenges match any quality range.
+    pub fn matches(&self, challenge: &Challenge) -> bool {
+        let contains = |field: &str, query: &Option<String>| {
+            query
+                .as_ref()
+                .is_none_or(|query| field.to_lowercase().contains(&query.to_lowercase()))
         };
-
-        let (quality_min, quality_max) = match &self.quality_range {
-            Some(range) => (range[0].to_string(), range[1].to_string()),
-            None => (String::new(), String::new()),
+        let in_range = |value: f32, range: Option<[i32; 2]>| {
+            range.is_none_or(|[min, max]| value >= min as f32 && value <= max as f32)
         };
 
-        let language = self
-            .language
-            .as_ref()
-            .map(|l| l.to_string())
-            .unwrap_or_default();
-        let arch = self
-            .arch
-            .as_ref()
-            .map(|a| a.to_string())
-            .unwrap_or_default();
-        let platform = self
-            .platform
-            .as_ref()
-            .map(|p| p.to_string())
-            .unwrap_or_default();
+        contains(&challenge.name, &self.name)
+            && contains(&challenge.author, &self.author)
+            && in_range(challenge.difficulty, self.difficulty_range)
+            && (challenge.quality == UNRATED || in_range(challenge.quality, self.quality_range))
+            && same_kind(&challenge.language, self.language.as_ref())
+            && same_kind(&challenge.arch, self.arch.as_ref())
+            && same_kind(&challenge.platform, self.platform.as_ref())
+    }
 
-        write!(
-            f,
-            "name={}&author={}&difficulty-min={}&difficulty-max={}&quality-min={}&quality-max={}&token={}&language={}&arch={}&platform={}",
-            name,
-            author,
-            difficulty_min,
-            difficulty_max,
-            quality_min,
-            quality_max,
-            self.token,
-            language,
-            arch,
-            platform
-        )
+    /// Fields of the search form as crackmes.one expects them, left for
+    /// reqwest to URL-encode.
+    pub fn form(&self, page: usize) -> Vec<(&'static str, String)> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        let bound = |range: Option<[i32; 2]>, index: usize| {
+            range
+                .map(|range| range[index].to_string())
+                .unwrap_or_default()
+        };
+        let label = |value: Option<String>| value.unwrap_or_default();
+
+        vec![
+            ("name", text(&self.name)),
+            ("author", text(&self.author)),
+            ("difficulty-min", bound(self.difficulty_range, 0)),
+            ("difficulty-max", bound(self.difficulty_range, 1)),
+            ("quality-min", bound(self.quality_range, 0)),
+            ("quality-max", bound(self.quality_range, 1)),
+            ("token", self.token.clone()),
+            (
+                "language",
+                label(self.language.as_ref().map(ToString::to_string)),
+            ),
+            ("arch", label(self.arch.as_ref().map(ToString::to_string))),
+            (
+                "platform",
+                label(self.platform.as_ref().map(ToString::to_string)),
+            ),
+            ("page", page.to_string()),
+        ]
     }
 }
 
-#[derive(Debug)]
+// `Other` stands for every label we do not know
+fn same_kind<T>(value: &T, expected: Option<&T>) -> bool {
+    expected.is_none_or(|expected| discriminant(expected) == discriminant(value))
+}
+
+// Languages, architectures and platforms serialize as the label crackmes.one
+// uses, e.g. in JSON output. Labels we do not know are kept as is in `Other`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
 pub enum Language {
     Ccpp,
     Assembler,
@@ -72,7 +97,7 @@ pub enum Language {
     Borland,
     Pascal,
     Dotnet,
-    Other,
+    Other(String),
 }
 
 impl From<&str> for Language {
@@ -88,11 +113,30 @@ impl From<&str> for Language {
             "Borland Delphi" => Language::Borland,
             "Turbo Pascal" => Language::Pascal,
             ".NET" => Language::Dotnet,
-            _ => Language::Other,
+            "" => Language::default(),
+            _ => Language::Other(s.to_string()),
         }
     }
 }
 
+impl Default for Language {
+    fn default() -> Self {
+        Language::Other(String::from("Unspecified/other"))
+    }
+}
+
+impl From<String> for Language {
+    fn from(s: String) -> Self {
+        Language::from(s.as_str())
+    }
+}
+
+impl From<Language> for String {
+    fn from(value: Language) -> Self {
+        value.to_string()
+    }
+}
+
 impl std::fmt::Display for Language {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -106,13 +150,14 @@ impl std::fmt::Display for Language {
             Language::Borland => "Borland Delphi",
             Language::Pascal => "Turbo Pascal",
             Language::Dotnet => ".NET",
-            Language::Other => "Unspecified/other",
+            Language::Other(label) => label,
         };
         write!(f, "{}", s)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
 pub enum Arch {
     X86,
     X8664,
@@ -120,7 +165,7 @@ pub enum Arch {
     Arm,
     Mips,
     RiscV,
-    Other,
+    Other(String),
 }
 
 impl From<&str> for Arch {
@@ -132,11 +177,30 @@ impl From<&str> for Arch {
             "ARM" => Arch::Arm,
             "MIPS" => Arch::Mips,
             "RISC-V" => Arch::RiscV,
-            _ => Arch::Other,
+            "" => Arch::default(),
+            _ => Arch::Other(s.to_string()),
         }
     }
 }
 
+impl Default for Arch {
+    fn default() -> Self {
+        Arch::Other(String::from("other"))
+    }
+}
+
+impl From<String> for Arch {
+    fn from(s: String) -> Self {
+        Arch::from(s.as_str())
+    }
+}
+
+impl From<Arch> for String {
+    fn from(value: Arch) -> Self {
+        value.to_string()
+    }
+}
+
 impl std::fmt::Display for Arch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -146,13 +210,14 @@ impl std::fmt::Display for Arch {
             Arch::Arm => "ARM",
             Arch::Mips => "MIPS",
             Arch::RiscV => "RISC-V",
-            Arch::Other => "other",
+            Arch::Other(label) => label,
         };
         write!(f, "{}", s)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
 pub enum Platform {
     Dos,
     MacOSX,
@@ -163,7 +228,7 @@ pub enum Platform {
     Windows7,
     Android,
     Ios,
-    Other,
+    Other(String),
 }
 
 impl From<&str> for Platform {
@@ -178,11 +243,30 @@ impl From<&str> for Platform {
             "Windows 7 Only" => Platform::Windows7,
             "Android" => Platform::Android,
             "iOS" => Platform::Ios,
-            _ => Platform::Other,
+            "" => Platform::default(),
+            _ => Platform::Other(s.to_string()),
         }
     }
 }
 
+impl Default for Platform {
+    fn default() -> Self {
+        Platform::Other(String::from("Unspecified/other"))
+    }
+}
+
+impl From<String> for Platform {
+    fn from(s: String) -> Self {
+        Platform::from(s.as_str())
+    }
+}
+
+impl From<Platform> for String {
+    fn from(value: Platform) -> Self {
+        value.to_string()
+    }
+}
+
 impl std::fmt::Display for Platform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -195,186 +279,1151 @@ impl std::fmt::Display for Platform {
             Platform::Windows7 => "Windows 7 Only",
             Platform::Android => "Android",
             Platform::Ios => "iOS",
-            Platform::Other => "Unspecified/other",
+            Platform::Other(label) => label,
         };
         write!(f, "{}", s)
     }
 }
 
-// curl -X GET -I 'https://crackmes.one/search'
-pub async fn get_search_token() -> Result<(String, String), CoreError> {
-    let client = reqwest::Client::new();
+/// Where crackmes.one lives. Every request takes the base URL as a parameter
+/// so that tests can point the scraper at a local server instead.
+pub const BASE_URL: &str = "https://crackmes.one";
+
+/// Sends a request for `resource`, turning an error status into
+/// `CoreError::Status`.
+pub async fn send(resource: Resource, request: RequestBuilder) -> Result<Response, CoreError> {
+    let (client, request) = request.build_split();
+    let request = request.map_err(|source| CoreError::Request {
+        resource,
+        url: source.url().map(|url| url.to_string()).unwrap_or_default(),
+        source,
+    })?;
+    let url = request.url().to_string();
     let response = client
-        .get("https://crackmes.one/search")
-        .send()
+        .execute(request)
         .await
-        .map_err(|_| CoreError::GetToken)?;
+        .map_err(CoreError::request(resource, &url))?;
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(CoreError::Status {
+            resource,
+            url,
+            status,
+        });
+    }
+    Ok(response)
+}
+
+// curl -X GET -I 'https://crackmes.one/search'
+/// The request for the search page, which hands out the session tokens.
+pub fn search_token_request(client: &Client, base_url: &str) -> RequestBuilder {
+    client.get(format!("{}/search", base_url))
+}
 
-    let set_cookie = response
+pub async fn get_search_token(base_url: &str) -> Result<(String, String), CoreError> {
+    let url = format!("{}/search", base_url);
+    let response = send(
+        Resource::SearchPage,
+        search_token_request(&Client::new(), base_url),
+    )
+    .await?;
+
+    let cookie_token = response
         .headers()
         .get("set-cookie")
         .and_then(|header| header.to_str().ok())
-        .map(|s| s.to_string())
-        .ok_or(CoreError::GetToken)?;
+        .and_then(read_cookie_token)
+        .map(String::from)
+        .ok_or_else(|| {
+            CoreError::parse(
+                Resource::SearchPage,
+                &url,
+                "a session cookie in the Set-Cookie header",
+            )
+        })?;
 
-    let cookies: Vec<&str> = set_cookie.split(";").collect();
-    let token_parts: Vec<&str> = cookies[0].split("=").collect();
-    let cookie_token = token_parts[1].to_string();
+    let body = response
+        .text()
+        .await
+        .map_err(CoreError::request(Resource::SearchPage, &url))?;
+    let html_token = read_html_token(&body).ok_or_else(|| {
+        CoreError::parse(
+            Resource::SearchPage,
+            &url,
+            r#"an input tag with id="token" and a value"#,
+        )
+    })?;
 
-    let body = response.text().await.map_err(|_| CoreError::GetToken)?;
+    Ok((cookie_token, html_token.to_string()))
+}
+
+/// Reads the session token out of the `Set-Cookie` header of the search page.
+pub fn read_cookie_token(set_cookie: &str) -> Option<&str> {
+    let (_, token) = set_cookie.split(';').next()?.split_once('=')?;
+    Some(token.trim()).filter(|token| !token.is_empty())
+}
 
-    // Find the input tag with id="token" and extract its value attribute
-    let html_token = body
-        .lines()
+/// Reads the form token, the value of the input tag with id="token".
+pub fn read_html_token(body: &str) -> Option<&str> {
+    body.lines()
         .find(|line| line.contains(r#"id="token""#))
         .and_then(|line| {
             line.split("value=\"")
                 .nth(1)
                 .and_then(|s| s.split('"').next())
         })
-        .ok_or(CoreError::GetToken)?;
+}
+
+/// One page of search results, as returned by crackmes.one.
+pub struct SearchPage {
+    pub challenges: Vec<Challenge>,
+    pub page: usize,
+    pub has_next: bool,
+    /// What the parser could not make sense of on this page.
+    pub issues: Vec<ParseIssue>,
+    /// Sources that could not be searched, each with the reason, when the
+    /// page merges several of them.
+    pub unavailable: Vec<String>,
+}
+
+impl SearchPage {
+    /// One line about what went wrong while reading the page, if anything.
+    pub fn warning(&self) -> Option<String> {
+        if !self.unavailable.is_empty() {
+            return Some(format!(
+                "Some sources could not be searched: {}",
+                self.unavailable.join(", ")
+            ));
+        }
+        let first = self.issues.first()?;
+        Some(match self.issues.len() {
+            1 => format!("The results page looks different: {}", first),
+            count => format!(
+                "The results page looks different: {} (and {} more)",
+                first,
+                count - 1
+            ),
+        })
+    }
+}
+
+/// Columns of the search results table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Author,
+    Language,
+    Arch,
+    Difficulty,
+    Quality,
+    Platform,
+    Date,
+    Writeups,
+    Comments,
+}
+
+impl Column {
+    /// The order crackmes.one uses, assumed when the table has no header.
+    const USUAL_ORDER: [Column; 10] = [
+        Column::Name,
+        Column::Author,
+        Column::Language,
+        Column::Arch,
+        Column::Difficulty,
+        Column::Quality,
+        Column::Platform,
+        Column::Date,
+        Column::Writeups,
+        Column::Comments,
+    ];
+
+    /// A row cannot become a challenge without these.
+    const REQUIRED: [Column; 4] = [
+        Column::Name,
+        Column::Author,
+        Column::Difficulty,
+        Column::Quality,
+    ];
 
-    Ok((cookie_token.to_string(), html_token.to_string()))
+    fn from_header(header: &str) -> Option<Self> {
+        match header.trim().to_lowercase().as_str() {
+            "name" => Some(Column::Name),
+            "author" => Some(Column::Author),
+            "language" | "lang" => Some(Column::Language),
+            "arch" | "architecture" => Some(Column::Arch),
+            "difficulty" => Some(Column::Difficulty),
+            "quality" => Some(Column::Quality),
+            "platform" | "os" => Some(Column::Platform),
+            "date" | "upload date" | "uploaded" => Some(Column::Date),
+            "writeups" | "solutions" => Some(Column::Writeups),
+            "comments" => Some(Column::Comments),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Column::Name => "name",
+            Column::Author => "author",
+            Column::Language => "language",
+            Column::Arch => "arch",
+            Column::Difficulty => "difficulty",
+            Column::Quality => "quality",
+            Column::Platform => "platform",
+            Column::Date => "date",
+            Column::Writeups => "writeups",
+            Column::Comments => "comments",
+        };
+        write!(f, "{}", label)
+    }
 }
 
-pub async fn get_challenge_list(
+/// Something on a results page that did not read as expected, most likely
+/// because the layout of the site changed. Rows are numbered from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseIssue {
+    /// The page has no results table at all, not even an empty one.
+    MissingTable,
+    /// The results table has no header, so the usual column order is assumed.
+    MissingHeader,
+    /// The header lacks a column every challenge needs, no row was read.
+    MissingColumn(Column),
+    /// A header the parser does not know, its column is ignored.
+    UnknownColumn(String),
+    /// A row with fewer cells than the header has columns.
+    ShortRow {
+        row: usize,
+        cells: usize,
+        expected: usize,
+    },
+    /// A cell that does not hold what its column should. The row is dropped
+    /// when the column is required, otherwise only that value is.
+    BadCell {
+        row: usize,
+        column: Column,
+        content: String,
+    },
+}
+
+impl std::fmt::Display for ParseIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseIssue::MissingTable => write!(f, "the page has no results table"),
+            ParseIssue::MissingHeader => write!(f, "the results table has no header"),
+            ParseIssue::MissingColumn(column) => {
+                write!(f, "the results table has no {} column", column)
+            }
+            ParseIssue::UnknownColumn(header) => {
+                write!(f, "the results table has an unknown column {:?}", header)
+            }
+            ParseIssue::ShortRow {
+                row,
+                cells,
+                expected,
+            } => write!(f, "row {} has {} cells, expected {}", row, cells, expected),
+            ParseIssue::BadCell {
+                row,
+                column,
+                content,
+            } => write!(f, "row {} has an unreadable {}: {:?}", row, column, content),
+        }
+    }
+}
+
+/// Where each column sits in a row, read from the table header.
+struct Columns {
+    cells: Vec<Option<Column>>,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            cells: Column::USUAL_ORDER.into_iter().map(Some).collect(),
+        }
+    }
+}
+
+impl Columns {
+    /// Reads the header of `table`, or returns `None` if it has none.
+    fn from_table(table: &ElementRef, issues: &mut Vec<ParseIssue>) -> Option<Self> {
+        let header_selector = Selector::parse("thead th, thead td").unwrap();
+        let headers: Vec<String> = table
+            .select(&header_selector)
+            .map(|header| header.text().collect::<String>().trim().to_string())
+            .collect();
+        if headers.is_empty() {
+            return None;
+        }
+
+        let cells = headers
+            .into_iter()
+            .map(|header| {
+                let column = Column::from_header(&header);
+                if column.is_none() && !header.is_empty() {
+                    issues.push(ParseIssue::UnknownColumn(header));
+                }
+                column
+            })
+            .collect();
+        Some(Self { cells })
+    }
+
+    fn position(&self, column: Column) -> Option<usize> {
+        self.cells.iter().position(|&cell| cell == Some(column))
+    }
+}
+
+/// Walks the paginated search results one page at a time.
+pub struct SearchCursor {
+    base_url: String,
     params: SearchParams,
     cookie_token: String,
-) -> Result<Vec<Challenge>, CoreError> {
-    let client = reqwest::Client::new();
+    next_page: usize,
+    exhausted: bool,
+}
 
-    let body = params.to_string();
-    let cookie_header = format!("gosess={}", cookie_token);
+impl SearchCursor {
+    pub fn new(base_url: &str, params: SearchParams, cookie_token: String) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            params,
+            cookie_token,
+            next_page: 1,
+            exhausted: false,
+        }
+    }
 
-    let response = client
-        .post("https://crackmes.one/search")
-        .header("Cookie", cookie_header)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(body)
-        .send()
+    /// Fetches the next page, or returns `None` once the last page has been read.
+    pub async fn next_page(&mut self) -> Result<Option<SearchPage>, CoreError> {
+        if self.exhausted {
+            return Ok(None);
+        }
+
+        let page = get_challenge_page(
+            &self.base_url,
+            &self.params,
+            &self.cookie_token,
+            self.next_page,
+        )
+        .await?;
+        self.next_page += 1;
+        if !page.has_next || page.challenges.is_empty() {
+            self.exhausted = true;
+        }
+        Ok(Some(page))
+    }
+}
+
+/// The search form submission for one page of results, within the session
+/// of `cookie_token`.
+pub fn search_request(
+    client: &Client,
+    base_url: &str,
+    params: &SearchParams,
+    cookie_token: &str,
+    page: usize,
+) -> RequestBuilder {
+    client
+        .post(format!("{}/search", base_url))
+        .header("Cookie", format!("gosess={}", cookie_token))
+        .form(&params.form(page))
+}
+
+pub async fn get_challenge_page(
+    base_url: &str,
+    params: &SearchParams,
+    cookie_token: &str,
+    page: usize,
+) -> Result<SearchPage, CoreError> {
+    let url = format!("{}/search", base_url);
+    let request = search_request(&Client::new(), base_url, params, cookie_token, page);
+    let html_text = send(Resource::ChallengeList, request)
+        .await?
+        .text()
         .await
-        .map_err(|e| {
-            eprintln!("Request error: {:?}", e);
-            CoreError::GetChallengeList
-        })?;
+        .map_err(CoreError::request(Resource::ChallengeList, &url))?;
 
-    let html_text = response.text().await.map_err(|e| {
-        eprintln!("Response text error: {:?}", e);
-        CoreError::GetChallengeList
-    })?;
+    Ok(parse_search_page(&html_text, page))
+}
 
-    // Debug: save HTML to file for inspection
-    eprintln!("Response HTML length: {}", html_text.len());
+pub fn parse_search_page(html_text: &str, page: usize) -> SearchPage {
+    let document = Html::parse_document(html_text);
+    let (challenges, issues) = parse_results_table(&document);
 
-    // Parse the HTML
-    let document = Html::parse_document(&html_text);
-    let row_selector = Selector::parse(r#"tbody#content-list tr"#).unwrap();
+    SearchPage {
+        challenges,
+        page,
+        has_next: has_next_page(&document, page),
+        issues,
+        unavailable: Vec::new(),
+    }
+}
+
+// Columns are looked up by their header rather than their position, so that
+// a new or moved column is noticed instead of shifting every field
+fn parse_results_table(document: &Html) -> (Vec<Challenge>, Vec<ParseIssue>) {
+    let body_selector = Selector::parse("tbody#content-list").unwrap();
+    let row_selector = Selector::parse("tr").unwrap();
 
     let mut challenges = Vec::new();
-    let mut row_count = 0;
+    let mut issues = Vec::new();
+    let Some(body) = document.select(&body_selector).next() else {
+        issues.push(ParseIssue::MissingTable);
+        return (challenges, issues);
+    };
+    let rows: Vec<ElementRef> = body.select(&row_selector).collect();
+    if rows.is_empty() {
+        return (challenges, issues);
+    }
 
-    for row in document.select(&row_selector) {
-        row_count += 1;
-        if let Some(challenge) = parse_challenge_row(&row) {
+    let header = body
+        .parent()
+        .and_then(ElementRef::wrap)
+        .and_then(|table| Columns::from_table(&table, &mut issues));
+    let columns = header.unwrap_or_else(|| {
+        issues.push(ParseIssue::MissingHeader);
+        Columns::default()
+    });
+
+    let missing: Vec<ParseIssue> = Column::REQUIRED
+        .into_iter()
+        .filter(|&column| columns.position(column).is_none())
+        .map(ParseIssue::MissingColumn)
+        .collect();
+    if !missing.is_empty() {
+        issues.extend(missing);
+        return (challenges, issues);
+    }
+
+    for (number, row) in rows.iter().enumerate() {
+        if let Some(challenge) = parse_challenge_row(row, &columns, number + 1, &mut issues) {
             challenges.push(challenge);
-        } else {
-            eprintln!("Failed to parse row {}", row_count);
         }
     }
+    (challenges, issues)
+}
 
-    eprintln!(
-        "Found {} rows, parsed {} challenges",
-        row_count,
-        challenges.len()
-    );
+// The pagination block links every reachable page, so there is a next page
+// whenever one of its links points at `page + 1`.
+fn has_next_page(document: &Html, page: usize) -> bool {
+    let link_selector = Selector::parse(".pagination a").unwrap();
+    let next = (page + 1).to_string();
+    let next_param = format!("page={}", next);
 
-    Ok(challenges)
+    document.select(&link_selector).any(|link| {
+        let text = link.text().collect::<String>();
+        let href = link.value().attr("href").unwrap_or_default();
+        text.trim() == next
+            || href.ends_with(&next_param)
+            || href.contains(&format!("{}&", next_param))
+    })
 }
 
-fn parse_challenge_row(row: &scraper::ElementRef) -> Option<Challenge> {
-    let td_selector = Selector::parse("td").unwrap();
+/// Turns a site-relative link such as `/crackme/<id>` into a full URL.
+pub fn absolute_url(url: &str) -> String {
+    url_on(BASE_URL, url)
+}
+
+/// Same as `absolute_url`, for a site served from `base_url`.
+pub fn url_on(base_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("{}{}", base_url, url)
+    }
+}
+
+pub async fn get_challenge_details(
+    base_url: &str,
+    url: &str,
+) -> Result<ChallengeDetails, CoreError> {
+    let html_text = get_page(&url_on(base_url, url)).await?;
+    Ok(parse_challenge_details(&html_text))
+}
+
+/// Fetches a challenge by the ID found at the end of its URL, along with the
+/// details shown on its page.
+pub async fn get_challenge(
+    base_url: &str,
+    id: &str,
+) -> Result<(Challenge, ChallengeDetails), CoreError> {
+    let url = format!("/crackme/{}", id);
+    let page_url = url_on(base_url, &url);
+    let html_text = get_page(&page_url).await?;
+
+    let challenge = parse_challenge_page(&html_text, url).ok_or_else(|| {
+        CoreError::parse(
+            Resource::ChallengePage,
+            &page_url,
+            "author, difficulty and quality fields",
+        )
+    })?;
+    Ok((challenge, parse_challenge_details(&html_text)))
+}
+
+async fn get_page(url: &str) -> Result<String, CoreError> {
+    send(Resource::ChallengePage, Client::new().get(url))
+        .await?
+        .text()
+        .await
+        .map_err(CoreError::request(Resource::ChallengePage, url))
+}
+
+// The challenge page lays its metadata out as "Label: value" paragraphs, so
+// fields are matched on their label rather than on their position.
+fn labelled_fields(document: &Html) -> Vec<(String, String)> {
+    let field_selector = Selector::parse("p, li").unwrap();
+
+    document
+        .select(&field_selector)
+        .filter_map(|field| {
+            let text = field.text().collect::<String>();
+            let (label, value) = text.split_once(':')?;
+            let value = value.trim();
+            if value.is_empty() {
+                return None;
+            }
+            Some((label.trim().to_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+pub fn parse_challenge_page(html_text: &str, url: String) -> Option<Challenge> {
+    let document = Html::parse_document(html_text);
+    let title_selector = Selector::parse("h3").unwrap();
+
+    let fields = labelled_fields(&document);
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let name = match field("name") {
+        Some(name) => name.to_string(),
+        None => document
+            .select(&title_selector)
+            .next()?
+            .text()
+            .collect::<String>()
+            .trim()
+            .to_string(),
+    };
+
+    Some(Challenge {
+        name,
+        url,
+        author: field("author")?.to_string(),
+        language: Language::from(field("language").unwrap_or_default()),
+        arch: Arch::from(field("arch").unwrap_or_default()),
+        difficulty: field("difficulty")?.parse().ok()?,
+        quality: field("quality")?.parse().ok()?,
+        platform: Platform::from(field("platform").unwrap_or_default()),
+        upload_date: field("upload").map(String::from),
+        writeups: field("writeups").and_then(|writeups| writeups.parse().ok()),
+    })
+}
+
+pub fn parse_challenge_details(html_text: &str) -> ChallengeDetails {
+    let document = Html::parse_document(html_text);
+    let heading_selector = Selector::parse("h1, h2, h3, h4, h5, h6, strong, span").unwrap();
+    let comment_selector = Selector::parse(".comment").unwrap();
     let a_selector = Selector::parse("a").unwrap();
+    let text_selector = Selector::parse("p").unwrap();
+    let writeup_selector = Selector::parse(".writeup").unwrap();
 
-    let tds: Vec<_> = row.select(&td_selector).collect();
+    let mut details = ChallengeDetails::default();
 
-    if tds.len() < 9 {
+    for (label, value) in labelled_fields(&document) {
+        let value = value.as_str();
+        match label.as_str() {
+            "upload" | "uploaded" | "upload date" => {
+                details.upload_date.get_or_insert_with(|| value.to_string());
+            }
+            "downloads" => {
+                details.downloads = details.downloads.or(value.parse().ok());
+            }
+            "writeups" => {
+                details.writeups = details.writeups.or(value.parse().ok());
+            }
+            "sha256" | "sha-256" | "hash" => {
+                details.hash.get_or_insert_with(|| value.to_lowercase());
+            }
+            _ => {}
+        }
+    }
+
+    // The description is the first element following its heading
+    details.description = document
+        .select(&heading_selector)
+        .find(|heading| heading.text().collect::<String>().trim() == "Description")
+        .and_then(|heading| {
+            heading
+                .next_siblings()
+                .filter_map(scraper::ElementRef::wrap)
+                .next()
+        })
+        .map(|description| description.text().collect::<String>().trim().to_string())
+        .filter(|description| !description.is_empty());
+
+    for comment in document.select(&comment_selector) {
+        let author = comment
+            .select(&a_selector)
+            .next()
+            .map(|a| a.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+        let text = comment
+            .select(&text_selector)
+            .map(|p| p.text().collect::<String>().trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !text.is_empty() {
+            details.comments.push(ChallengeComment { author, text });
+        }
+    }
+
+    for writeup in document.select(&writeup_selector) {
+        let links: Vec<_> = writeup.select(&a_selector).collect();
+        let author = links
+            .iter()
+            .find(|a| {
+                a.value()
+                    .attr("href")
+                    .is_some_and(|href| href.starts_with("/user/"))
+            })
+            .map(|a| a.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+        let url = links
+            .iter()
+            .filter_map(|a| a.value().attr("href"))
+            .find(|href| href.contains("writeup"));
+
+        if let Some(url) = url {
+            details.writeup_links.push(Writeup {
+                author,
+                url: url.to_string(),
+            });
+        }
+    }
+
+    details
+}
+
+/// Fetches a writeup and returns it as displayable text.
+///
+/// Markdown and plain text writeups are returned as is, HTML ones are reduced
+/// to their text content. Binary writeups (PDF, archives...) can only be
+/// downloaded.
+pub async fn get_writeup_text(base_url: &str, url: &str) -> Result<String, CoreError> {
+    let url = url_on(base_url, url);
+    let response = send(Resource::Writeup, Client::new().get(&url)).await?;
+
+    let is_html = response
+        .headers()
+        .get("content-type")
+        .and_then(|header| header.to_str().ok())
+        .is_some_and(|content_type| content_type.contains("text/html"));
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(CoreError::request(Resource::Writeup, &url))?;
+    let text = String::from_utf8(body.to_vec())
+        .map_err(|_| CoreError::parse(Resource::Writeup, &url, "UTF-8 text"))?;
+
+    if is_html {
+        Ok(html_to_text(&text))
+    } else {
+        Ok(text)
+    }
+}
+
+pub fn html_to_text(html_text: &str) -> String {
+    let document = Html::parse_document(html_text);
+    let block_selector = Selector::parse("h1, h2, h3, h4, h5, h6, p, li, pre").unwrap();
+
+    document
+        .select(&block_selector)
+        .map(|block| {
+            let text = block.text().collect::<String>();
+            match block.value().name() {
+                "h1" => format!("# {}", text.trim()),
+                "h2" => format!("## {}", text.trim()),
+                "h3" | "h4" | "h5" | "h6" => format!("### {}", text.trim()),
+                "li" => format!("- {}", text.trim()),
+                "pre" => format!("```\n{}\n```", text.trim_end()),
+                _ => text.trim().to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn parse_challenge_row(
+    row: &ElementRef,
+    columns: &Columns,
+    number: usize,
+    issues: &mut Vec<ParseIssue>,
+) -> Option<Challenge> {
+    let td_selector = Selector::parse("td").unwrap();
+    let a_selector = Selector::parse("a").unwrap();
+
+    let cells: Vec<ElementRef> = row.select(&td_selector).collect();
+    if cells.len() < columns.cells.len() {
+        issues.push(ParseIssue::ShortRow {
+            row: number,
+            cells: cells.len(),
+            expected: columns.cells.len(),
+        });
         return None;
     }
 
-    // Extract name and URL from first td
-    let first_td = tds.first()?;
-    let link = first_td.select(&a_selector).next()?;
-    let url = link.value().attr("href")?.to_string();
-    let name = link.text().collect::<String>().trim().to_string();
+    let cell = |column: Column| {
+        columns
+            .position(column)
+            .and_then(|position| cells.get(position))
+    };
+    let text = |column: Column| {
+        cell(column)
+            .map(|cell| cell.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut bad_cell = |column: Column| {
+        issues.push(ParseIssue::BadCell {
+            row: number,
+            column,
+            content: text(column),
+        });
+    };
 
-    // Extract author from second td
-    let author_td = tds.get(1)?;
-    let author = author_td
-        .select(&a_selector)
-        .next()?
-        .text()
-        .collect::<String>()
-        .trim()
-        .to_string();
-
-    // Extract language from third td
-    let language_td = tds.get(2)?;
-    let language_str = language_td.text().collect::<String>().trim().to_string();
-    let language = Language::from(language_str.as_str());
-
-    // Extract arch from fourth td
-    let arch_td = tds.get(3)?;
-    let arch_str = arch_td.text().collect::<String>().trim().to_string();
-    let arch = Arch::from(arch_str.as_str());
-
-    // Extract difficulty from fifth td
-    let difficulty_td = tds.get(4)?;
-    let difficulty = difficulty_td
-        .text()
-        .collect::<String>()
-        .trim()
-        .parse::<f32>()
-        .ok()?;
-
-    // Extract quality from sixth td
-    let quality_td = tds.get(5)?;
-    let quality = quality_td
-        .text()
-        .collect::<String>()
-        .trim()
-        .parse::<f32>()
-        .ok()?;
+    let link = cell(Column::Name).and_then(|cell| cell.select(&a_selector).next());
+    let Some((url, name)) = link.and_then(|link| {
+        let url = link.value().attr("href")?.to_string();
+        Some((url, link.text().collect::<String>().trim().to_string()))
+    }) else {
+        bad_cell(Column::Name);
+        return None;
+    };
 
-    // Extract platform from seventh td
-    let platform_td = tds.get(6)?;
-    let platform_str = platform_td.text().collect::<String>().trim().to_string();
-    let platform = Platform::from(platform_str.as_str());
+    let author = cell(Column::Author)
+        .and_then(|cell| cell.select(&a_selector).next())
+        .map(|link| link.text().collect::<String>().trim().to_string());
+    let Some(author) = author else {
+        bad_cell(Column::Author);
+        return None;
+    };
 
-    // Extract writeups from ninth td (skip eighth which is date)
+    let Ok(difficulty) = text(Column::Difficulty).parse::<f32>() else {
+        bad_cell(Column::Difficulty);
+        return None;
+    };
+    let Ok(quality) = text(Column::Quality).parse::<f32>() else {
+        bad_cell(Column::Quality);
+        return None;
+    };
+
+    // Optional columns only lose their own value when they do not read
+    let upload_date = Some(text(Column::Date)).filter(|date| !date.is_empty());
+    let upload_date = match upload_date {
+        Some(date) if parse_date(&date).is_none() => {
+            bad_cell(Column::Date);
+            None
+        }
+        date => date,
+    };
+    let writeups = Some(text(Column::Writeups)).filter(|writeups| !writeups.is_empty());
+    let writeups = match writeups.map(|writeups| writeups.parse::<u32>()) {
+        Some(Ok(writeups)) => Some(writeups),
+        Some(Err(_)) => {
+            bad_cell(Column::Writeups);
+            None
+        }
+        None => None,
+    };
 
     Some(Challenge {
         name,
         url,
         author,
-        language,
-        arch,
+        language: Language::from(text(Column::Language).as_str()),
+        arch: Arch::from(text(Column::Arch).as_str()),
         difficulty,
         quality,
-        platform,
+        platform: Platform::from(text(Column::Platform).as_str()),
+        upload_date,
+        writeups,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_server::{CHALLENGE_ID, COOKIE_TOKEN, HTML_TOKEN, MockServer, Route};
+
+    #[test]
+    fn test_parse_challenge_details_reads_labelled_fields() {
+        let html = r#"<html><body>
+            <p>Upload: 12:00 PM 01/02/2024</p>
+            <p>Downloads: 1234</p>
+            <p>Writeups: 3</p>
+            <p>SHA256: ABCDEF0123</p>
+            <h3>Description</h3>
+            <p>Find the password.</p>
+            <div class="comment"><a href="/user/bob">bob</a><p>Nice one!</p></div>
+            <div class="writeup">
+                <a href="/user/alice">alice</a>
+                <a href="/static/writeup/abc123.md">Download</a>
+            </div>
+        </body></html>"#;
+
+        let details = parse_challenge_details(html);
+        assert_eq!(details.upload_date.as_deref(), Some("12:00 PM 01/02/2024"));
+        assert_eq!(details.downloads, Some(1234));
+        assert_eq!(details.writeups, Some(3));
+        assert_eq!(details.hash.as_deref(), Some("abcdef0123"));
+        assert_eq!(details.description.as_deref(), Some("Find the password."));
+        assert_eq!(details.comments.len(), 1);
+        assert_eq!(details.comments[0].author, "bob");
+        assert_eq!(details.comments[0].text, "Nice one!");
+        assert_eq!(details.writeup_links.len(), 1);
+        assert_eq!(details.writeup_links[0].author, "alice");
+        assert_eq!(details.writeup_links[0].url, "/static/writeup/abc123.md");
+    }
+
+    #[test]
+    fn test_html_to_text_keeps_markdown_structure() {
+        let html = "<h1>Solution</h1><p>Patch the jump.</p><pre>jz 0x401000</pre>";
+        assert_eq!(
+            html_to_text(html),
+            "# Solution\n\nPatch the jump.\n\n```\njz 0x401000\n```"
+        );
+    }
+
+    #[test]
+    fn test_parse_challenge_page_reads_row_fields() {
+        let html = r#"<html><body>
+            <h3>easy_keygen</h3>
+            <p>Author: <a href="/user/bob">bob</a></p>
+            <p>Language: Rust</p>
+            <p>Arch: x86-64</p>
+            <p>Platform: Unix/linux etc.</p>
+            <p>Difficulty: 2.5</p>
+            <p>Quality: 4.0</p>
+        </body></html>"#;
+
+        let challenge = parse_challenge_page(html, String::from("/crackme/abc123")).unwrap();
+        assert_eq!(challenge.name, "easy_keygen");
+        assert_eq!(challenge.author, "bob");
+        assert!(matches!(challenge.language, Language::Rust));
+        assert!(matches!(challenge.arch, Arch::X8664));
+        assert!(matches!(challenge.platform, Platform::Unix));
+        assert_eq!(challenge.difficulty, 2.5);
+        assert_eq!(challenge.id(), "abc123");
+    }
+
+    #[test]
+    fn test_has_next_page_follows_pagination_links() {
+        let html = r#"<html><body>
+            <ul class="pagination">
+                <li><a href="/search?page=1">1</a></li>
+                <li><a href="/search?page=2">2</a></li>
+            </ul>
+        </body></html>"#;
+
+        let first = parse_search_page(html, 1);
+        assert!(first.has_next, "Expected a next page after page 1");
+
+        let last = parse_search_page(html, 2);
+        assert!(!last.has_next, "Expected page 2 to be the last page");
+    }
+
+    fn parse_row(cells: &str) -> Option<Challenge> {
+        let html = format!(
+            r#"<table><tbody id="content-list"><tr>{}</tr></tbody></table>"#,
+            cells
+        );
+        let document = Html::parse_document(&html);
+        let row_selector = Selector::parse("tbody#content-list tr").unwrap();
+        let row = document.select(&row_selector).next().unwrap();
+        parse_challenge_row(&row, &Columns::default(), 1, &mut Vec::new())
+    }
+
+    fn parse_table(headers: &[&str], rows: &[&str]) -> (Vec<Challenge>, Vec<ParseIssue>) {
+        let headers: String = headers
+            .iter()
+            .map(|header| format!("<th>{}</th>", header))
+            .collect();
+        let rows: String = rows.iter().map(|row| format!("<tr>{}</tr>", row)).collect();
+        let html = format!(
+            r#"<table><thead><tr>{}</tr></thead><tbody id="content-list">{}</tbody></table>"#,
+            headers, rows
+        );
+        parse_results_table(&Html::parse_document(&html))
+    }
+
+    #[test]
+    fn test_parse_challenge_row() {
+        const NAME: &str = r#"<td><a href="/crackme/abc123"> easy_keygen </a></td>"#;
+        const AUTHOR: &str = r#"<td><a href="/user/bob">bob</a></td>"#;
+        const REST: &str = "<td>Rust</td><td>x86-64</td><td>2.5</td><td>4.0</td>\
+            <td>Unix/linux etc.</td><td>12:00 PM 01/02/2024</td><td>1</td><td>3</td>";
+
+        // Name and upload date of the parsed challenge
+        type Parsed<'a> = Option<(&'a str, Option<&'a str>)>;
+        let cases: Vec<(&str, String, Parsed)> = vec![
+            (
+                "complete row",
+                format!("{}{}{}", NAME, AUTHOR, REST),
+                Some(("easy_keygen", Some("12:00 PM 01/02/2024"))),
+            ),
+            (
+                "empty date",
+                format!(
+                    "{}{}{}",
+                    NAME,
+                    AUTHOR,
+                    REST.replace("12:00 PM 01/02/2024", " ")
+                ),
+                Some(("easy_keygen", None)),
+            ),
+            (
+                "too few cells",
+                format!("{}{}<td>Rust</td>", NAME, AUTHOR),
+                None,
+            ),
+            (
+                "name without link",
+                format!("<td>easy_keygen</td>{}{}", AUTHOR, REST),
+                None,
+            ),
+            (
+                "link without href",
+                format!("<td><a>easy_keygen</a></td>{}{}", AUTHOR, REST),
+                None,
+            ),
+            (
+                "author without link",
+                format!("{}<td>bob</td>{}", NAME, REST),
+                None,
+            ),
+            (
+                "difficulty not a number",
+                format!("{}{}{}", NAME, AUTHOR, REST.replace("2.5", "hard")),
+                None,
+            ),
+            (
+                "quality missing",
+                format!("{}{}{}", NAME, AUTHOR, REST.replace("4.0", "")),
+                None,
+            ),
+            ("empty row", String::new(), None),
+        ];
+
+        for (case, cells, expected) in cases {
+            let parsed = parse_row(&cells);
+            let actual = parsed
+                .as_ref()
+                .map(|challenge| (challenge.name.as_str(), challenge.upload_date.as_deref()));
+            assert_eq!(actual, expected, "{}", case);
+        }
+
+        let challenge = parse_row(&format!("{}{}{}", NAME, AUTHOR, REST)).unwrap();
+        assert_eq!(challenge.url, "/crackme/abc123");
+        assert_eq!(challenge.author, "bob");
+        assert!(matches!(challenge.language, Language::Rust));
+        assert!(matches!(challenge.arch, Arch::X8664));
+        assert!(matches!(challenge.platform, Platform::Unix));
+        assert_eq!((challenge.difficulty, challenge.quality), (2.5, 4.0));
+        assert_eq!(challenge.writeups, Some(1));
+    }
+
+    #[test]
+    fn test_parse_results_table_maps_columns_by_header() {
+        let (challenges, issues) = parse_table(
+            &[
+                "Author",
+                "Name",
+                "Quality",
+                "Difficulty",
+                "Date",
+                "Writeups",
+                "Rank",
+            ],
+            &[
+                r#"<td><a href="/user/bob">bob</a></td><td><a href="/crackme/abc123">easy_keygen</a></td><td>4.0</td><td>2.5</td><td>12:00 PM 01/02/2024</td><td>2</td><td>7</td>"#,
+                r#"<td><a href="/user/eve">eve</a></td><td><a href="/crackme/def456">vm_maze</a></td><td>5.0</td><td>4.0</td><td>yesterday</td><td>many</td><td>1</td>"#,
+            ],
+        );
+
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].author, "bob");
+        assert_eq!(
+            (challenges[0].difficulty, challenges[0].quality),
+            (2.5, 4.0)
+        );
+        assert_eq!(challenges[0].writeups, Some(2));
+        assert!(matches!(challenges[0].language, Language::Other(_)));
+        // Unreadable optional cells only lose their own value
+        assert_eq!(challenges[1].upload_date, None);
+        assert_eq!(challenges[1].writeups, None);
+        assert_eq!(
+            issues,
+            vec![
+                ParseIssue::UnknownColumn(String::from("Rank")),
+                ParseIssue::BadCell {
+                    row: 2,
+                    column: Column::Date,
+                    content: String::from("yesterday"),
+                },
+                ParseIssue::BadCell {
+                    row: 2,
+                    column: Column::Writeups,
+                    content: String::from("many"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_results_table_reports_layout_changes() {
+        let row = r#"<td><a href="/crackme/abc123">easy_keygen</a></td><td><a href="/user/bob">bob</a></td><td>2.5</td><td>4.0</td>"#;
+
+        let (challenges, issues) = parse_table(&["Name", "Author", "Rating", "Quality"], &[row]);
+        assert!(challenges.is_empty());
+        assert_eq!(
+            issues,
+            vec![
+                ParseIssue::UnknownColumn(String::from("Rating")),
+                ParseIssue::MissingColumn(Column::Difficulty),
+            ]
+        );
+
+        let (challenges, issues) = parse_table(
+            &["Name", "Author", "Difficulty", "Quality"],
+            &[row, "<td>broken</td>"],
+        );
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(
+            issues,
+            vec![ParseIssue::ShortRow {
+                row: 2,
+                cells: 1,
+                expected: 4,
+            }]
+        );
+
+        let (challenges, issues) = parse_table(&["Name", "Author", "Difficulty", "Quality"], &[]);
+        assert!(challenges.is_empty());
+        assert!(issues.is_empty(), "an empty search is not a layout change");
+
+        let page = parse_search_page(
+            &format!(
+                r#"<table><tbody id="content-list"><tr>{}</tr></tbody></table>"#,
+                row
+            ),
+            1,
+        );
+        assert_eq!(page.issues[0], ParseIssue::MissingHeader);
+        assert!(page.warning().unwrap().contains("no header"));
+
+        let page = parse_search_page("<html><body><p>Maintenance</p></body></html>", 1);
+        assert!(page.challenges.is_empty());
+        assert_eq!(page.issues, vec![ParseIssue::MissingTable]);
+        assert!(page.warning().unwrap().contains("no results table"));
+    }
+
+    #[tokio::test]
+    async fn test_get_search_token_reads_cookie_and_form() {
+        let server = MockServer::crackmes().await;
+        let (cookie_token, html_token) = get_search_token(&server.url()).await.unwrap();
+        assert_eq!(cookie_token, COOKIE_TOKEN);
+        assert_eq!(html_token, HTML_TOKEN);
+    }
+
+    #[tokio::test]
+    async fn test_get_search_token_needs_a_cookie() {
+        let page = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/search_token.html"
+        ))
+        .unwrap();
+        let server = MockServer::start(vec![Route::new("GET", "/search", page)]).await;
+        assert!(get_search_token(&server.url()).await.is_err());
+    }
 
     #[tokio::test]
-    async fn test_get_search_token_returns_something() {
-        let result = get_search_token().await;
-        assert!(result.is_ok(), "Expected Ok result, got error");
+    async fn test_search_cursor_walks_recorded_pages() {
+        let server = MockServer::crackmes().await;
+        let params = SearchParams {
+            name: Some(String::from("keygen")),
+            author: None,
+            difficulty_range: Some([1, 6]),
+            quality_range: Some([1, 6]),
+            language: None,
+            arch: None,
+            platform: None,
+            query: None,
+            token: String::from(HTML_TOKEN),
+        };
+        let mut cursor = SearchCursor::new(&server.url(), params, String::from(COOKIE_TOKEN));
+
+        let first = cursor.next_page().await.unwrap().unwrap();
+        let names: Vec<&str> = first.challenges.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["easy_keygen", "vm_maze"]);
+        assert!(first.has_next);
+        let second = cursor.next_page().await.unwrap().unwrap();
+        assert_eq!(second.challenges[0].name, "first_steps");
+        assert!(!second.has_next);
+        assert!(cursor.next_page().await.unwrap().is_none());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let cookie = format!("gosess={}", COOKIE_TOKEN);
+        assert_eq!(requests[0].header("cookie"), Some(cookie.as_str()));
+        assert!(requests[0].body.starts_with("name=keygen&"));
+        assert!(requests[0].body.contains(&format!("token={}", HTML_TOKEN)));
+        assert!(requests[1].body.ends_with("&page=2"));
+    }
+
+    #[test]
+    fn test_search_request_encodes_the_form() {
+        let params = SearchParams {
+            name: Some(String::from("a&b=c+d%")),
+            language: Some(Language::Ccpp),
+            ..crate::cli::SearchArgs::default().to_search_params(String::from("t"))
+        };
+        let request = search_request(&Client::new(), "http://localhost", &params, "c", 3)
+            .build()
+            .unwrap();
+        let body = std::str::from_utf8(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert!(body.starts_with("name=a%26b%3Dc%2Bd%25&"), "{}", body);
+        assert!(body.contains("&language=C%2FC%2B%2B&"), "{}", body);
+        assert!(body.ends_with("&page=3"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_get_challenge_reads_recorded_page() {
+        let server = MockServer::crackmes().await;
+        let (challenge, details) = get_challenge(&server.url(), CHALLENGE_ID).await.unwrap();
+        assert_eq!(challenge.name, "easy_keygen");
+        assert_eq!(challenge.id(), CHALLENGE_ID);
+        assert_eq!(
+            details.description.as_deref(),
+            Some("Write a keygen for the binary.")
+        );
+        assert_eq!(details.downloads, Some(1234));
+
+        let writeup = &details.writeup_links[0];
+        let text = get_writeup_text(&server.url(), &writeup.url).await.unwrap();
+        assert!(text.starts_with("# Solution"));
 
-        let (cookie_token, html_token) = result.unwrap();
-        assert!(!cookie_token.is_empty(), "Expected non-empty cookie token");
-        assert!(!html_token.is_empty(), "Expected non-empty HTML token");
+        let missing = get_challenge(&server.url(), "missing").await;
+        assert!(missing.is_err());
     }
 }
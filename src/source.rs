@@ -0,0 +1,967 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::{
+    challenge::{Challenge, ChallengeDetails, UNRATED, Writeup, file_sha256},
+    config::Config,
+    errors::{CoreError, Resource},
+    index::ChallengeIndex,
+    mirror::Mirror,
+    paths::ensure_within,
+    search::{
+        Arch, BASE_URL, Language, Platform, SearchPage, SearchParams, get_challenge,
+        get_challenge_details, get_challenge_page, get_search_token, get_writeup_text,
+        html_to_text,
+    },
+};
+
+const LOCAL_SCHEME: &str = "local://";
+
+/// Somewhere challenges come from. Every challenge URL belongs to exactly one
+/// source, which is asked for its details, archive and writeups.
+#[async_trait]
+pub trait ChallengeSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether `url` points to a challenge of this source.
+    fn owns(&self, url: &str) -> bool;
+
+    /// Whether fetching from this source goes over the network.
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    /// One page of the challenges matching `params`, pages start at 1. The
+    /// token of `params` is ignored, sources handle their own sessions.
+    async fn search(&self, params: &SearchParams, page: usize) -> Result<SearchPage, CoreError>;
+
+    async fn challenge(&self, url: &str) -> Result<Challenge, CoreError>;
+
+    async fn details(&self, url: &str) -> Result<ChallengeDetails, CoreError>;
+
+    /// Writes the archive of `challenge` to `destination` and returns its
+    /// SHA-256.
+    async fn download(
+        &self,
+        challenge: &Challenge,
+        destination: &Path,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        cancelled: &AtomicBool,
+    ) -> Result<String, CoreError>;
+
+    /// Text of a writeup of the challenge at `challenge_url`, see
+    /// `get_writeup_text`.
+    async fn writeup_text(
+        &self,
+        challenge_url: &str,
+        writeup: &Writeup,
+    ) -> Result<String, CoreError>;
+
+    /// Saves a writeup into `directory` and returns where it was written.
+    async fn download_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError>;
+}
+
+/// The crackmes.one scraper.
+pub struct CrackmesOne {
+    base_url: String,
+    // Cookie and form tokens of the search session, fetched on first use
+    tokens: Mutex<Option<(String, String)>>,
+}
+
+impl Default for CrackmesOne {
+    fn default() -> Self {
+        Self::with_base_url(BASE_URL)
+    }
+}
+
+impl CrackmesOne {
+    /// A scraper for a copy of the site served from `base_url`.
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            tokens: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl ChallengeSource for CrackmesOne {
+    fn name(&self) -> &str {
+        "crackmes.one"
+    }
+
+    fn owns(&self, url: &str) -> bool {
+        url.starts_with('/') || url.starts_with(&self.base_url)
+    }
+
+    async fn search(&self, params: &SearchParams, page: usize) -> Result<SearchPage, CoreError> {
+        let mut tokens = self.tokens.lock().await;
+        let fresh = tokens.is_none();
+        if fresh {
+            *tokens = Some(get_search_token(&self.base_url).await?);
+        }
+        let search = |(cookie_token, html_token): &(String, String)| {
+            let params = SearchParams {
+                token: html_token.clone(),
+                ..params.clone()
+            };
+            let cookie_token = cookie_token.clone();
+            let base_url = self.base_url.clone();
+            async move { get_challenge_page(&base_url, &params, &cookie_token, page).await }
+        };
+
+        let result = search(tokens.as_ref().unwrap()).await;
+        if result.is_ok() || fresh {
+            return result;
+        }
+        // The tokens may have expired, fetch fresh ones and retry once
+        let renewed = tokens.insert(get_search_token(&self.base_url).await?);
+        search(renewed).await
+    }
+
+    async fn challenge(&self, url: &str) -> Result<Challenge, CoreError> {
+        let id = url.trim_end_matches('/').rsplit('/').next().unwrap_or(url);
+        Ok(get_challenge(&self.base_url, id).await?.0)
+    }
+
+    async fn details(&self, url: &str) -> Result<ChallengeDetails, CoreError> {
+        get_challenge_details(&self.base_url, url).await
+    }
+
+    async fn download(
+        &self,
+        challenge: &Challenge,
+        destination: &Path,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        cancelled: &AtomicBool,
+    ) -> Result<String, CoreError> {
+        challenge
+            .download(
+                &self.base_url,
+                destination,
+                &|downloaded, total| progress(downloaded, total),
+                cancelled,
+            )
+            .await
+    }
+
+    async fn writeup_text(
+        &self,
+        _challenge_url: &str,
+        writeup: &Writeup,
+    ) -> Result<String, CoreError> {
+        get_writeup_text(&self.base_url, &writeup.url).await
+    }
+
+    async fn download_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        writeup.download(&self.base_url, challenge, directory).await
+    }
+}
+
+/// A local directory source, as configured in `config.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalSourceConfig {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Metadata of `<name>.zip`, read from `<name>.toml` next to it.
+///
+/// ```toml
+/// name = "Internal keygen"
+/// author = "red team"
+/// language = "C/C++"
+/// arch = "x86-64"
+/// platform = "Unix/linux etc."
+/// difficulty = 2.5
+/// description = "Our onboarding keygen"
+///
+/// [[writeups]]
+/// author = "alice"
+/// file = "keygen-solution.md"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Sidecar {
+    name: Option<String>,
+    author: Option<String>,
+    language: Option<Language>,
+    arch: Option<Arch>,
+    platform: Option<Platform>,
+    difficulty: Option<f32>,
+    quality: Option<f32>,
+    uploaded: Option<String>,
+    description: Option<String>,
+    writeups: Vec<SidecarWriteup>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SidecarWriteup {
+    author: String,
+    /// Path relative to the source directory.
+    file: String,
+}
+
+struct LocalChallenge {
+    challenge: Challenge,
+    archive: PathBuf,
+    description: Option<String>,
+    writeups: Vec<Writeup>,
+}
+
+/// Challenges hosted in a directory of archives, each optionally described
+/// by a TOML sidecar. Archives without one are listed under their file name.
+pub struct LocalDirectory {
+    name: String,
+    root: PathBuf,
+    challenges: Vec<LocalChallenge>,
+}
+
+impl LocalDirectory {
+    /// Indexes the archives of `root`, sorted by file name.
+    pub fn open(name: &str, root: &Path) -> Result<Self, CoreError> {
+        let mut archives: Vec<PathBuf> = fs::read_dir(root)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "zip"))
+            .collect();
+        archives.sort();
+
+        let mut directory = Self {
+            name: name.to_string(),
+            root: root.to_path_buf(),
+            challenges: Vec::new(),
+        };
+        for archive in archives {
+            let path = archive.with_extension("toml");
+            let sidecar = match fs::read_to_string(&path) {
+                Ok(content) => toml::from_str(&content)
+                    .map_err(|source| CoreError::InvalidSource { path, source })?,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Sidecar::default(),
+                Err(error) => return Err(error.into()),
+            };
+            let challenge = directory.load(archive, sidecar);
+            directory.challenges.push(challenge);
+        }
+        Ok(directory)
+    }
+
+    fn load(&self, archive: PathBuf, sidecar: Sidecar) -> LocalChallenge {
+        let stem = archive
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let writeups: Vec<Writeup> = sidecar
+            .writeups
+            .into_iter()
+            .map(|writeup| Writeup {
+                author: writeup.author,
+                url: self.url(&writeup.file),
+            })
+            .collect();
+
+        LocalChallenge {
+            challenge: Challenge {
+                language: sidecar.language.unwrap_or_default(),
+                author: sidecar.author.unwrap_or_else(|| self.name.clone()),
+                name: sidecar.name.unwrap_or_else(|| stem.clone()),
+                url: self.url(&stem),
+                arch: sidecar.arch.unwrap_or_default(),
+                difficulty: sidecar.difficulty.unwrap_or(1.0),
+                quality: sidecar.quality.unwrap_or(UNRATED),
+                platform: sidecar.platform.unwrap_or_default(),
+                upload_date: sidecar.uploaded,
+                writeups: Some(writeups.len() as u32),
+            },
+            archive,
+            description: sidecar.description,
+            writeups,
+        }
+    }
+
+    fn url(&self, file: &str) -> String {
+        format!("{}{}/{}", LOCAL_SCHEME, self.name, file)
+    }
+
+    fn find(&self, url: &str) -> Result<&LocalChallenge, CoreError> {
+        self.challenges
+            .iter()
+            .find(|local| local.challenge.url == url)
+            .ok_or_else(|| CoreError::UnknownChallenge(url.to_string()))
+    }
+
+    // Writeups may only point inside the source directory
+    fn writeup_path(&self, writeup: &Writeup) -> Result<PathBuf, CoreError> {
+        let file = writeup
+            .url
+            .strip_prefix(&self.url(""))
+            .ok_or_else(|| CoreError::UnsafePath(PathBuf::from(&writeup.url)))?;
+        let path = self.root.join(file);
+        ensure_within(&self.root, &path)?;
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl ChallengeSource for LocalDirectory {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn owns(&self, url: &str) -> bool {
+        url.starts_with(&self.url(""))
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    // Everything fits on the first page
+    async fn search(&self, params: &SearchParams, page: usize) -> Result<SearchPage, CoreError> {
+        let challenges = match page {
+            1 => self
+                .challenges
+                .iter()
+                .map(|local| &local.challenge)
+                .filter(|challenge| params.matches(challenge))
+                .cloned()
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(SearchPage {
+            challenges,
+            page,
+            has_next: false,
+            issues: Vec::new(),
+            unavailable: Vec::new(),
+        })
+    }
+
+    async fn challenge(&self, url: &str) -> Result<Challenge, CoreError> {
+        Ok(self.find(url)?.challenge.clone())
+    }
+
+    async fn details(&self, url: &str) -> Result<ChallengeDetails, CoreError> {
+        let local = self.find(url)?;
+        Ok(ChallengeDetails {
+            description: local.description.clone(),
+            upload_date: local.challenge.upload_date.clone(),
+            writeups: Some(local.writeups.len() as u32),
+            writeup_links: local.writeups.clone(),
+            ..ChallengeDetails::default()
+        })
+    }
+
+    async fn download(
+        &self,
+        challenge: &Challenge,
+        destination: &Path,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        _cancelled: &AtomicBool,
+    ) -> Result<String, CoreError> {
+        let copied = tokio::fs::copy(&self.find(&challenge.url)?.archive, destination).await?;
+        progress(copied, Some(copied));
+        file_sha256(destination).await
+    }
+
+    async fn writeup_text(
+        &self,
+        _challenge_url: &str,
+        writeup: &Writeup,
+    ) -> Result<String, CoreError> {
+        let text = match tokio::fs::read_to_string(self.writeup_path(writeup)?).await {
+            Err(error) if error.kind() == std::io::ErrorKind::InvalidData => {
+                return Err(CoreError::parse(
+                    Resource::Writeup,
+                    &writeup.url,
+                    "UTF-8 text",
+                ));
+            }
+            result => result?,
+        };
+        if matches!(writeup.get_extension(), "html" | "htm") {
+            Ok(html_to_text(&text))
+        } else {
+            Ok(text)
+        }
+    }
+
+    async fn download_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        let path = writeup.destination(challenge, directory)?;
+        tokio::fs::copy(self.writeup_path(writeup)?, &path).await?;
+        writeup.settle(challenge, &path)
+    }
+}
+
+/// The local index, as browsed with `--offline`. Only listings and
+/// descriptions are indexed, archives and writeups still come from
+/// crackmes.one.
+pub struct IndexSource {
+    index: Mutex<ChallengeIndex>,
+    site: CrackmesOne,
+}
+
+impl IndexSource {
+    pub fn new(index: ChallengeIndex) -> Self {
+        Self {
+            index: Mutex::new(index),
+            site: CrackmesOne::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChallengeSource for IndexSource {
+    fn name(&self) -> &str {
+        "local index"
+    }
+
+    fn owns(&self, url: &str) -> bool {
+        self.site.owns(url)
+    }
+
+    // Everything fits on the first page
+    async fn search(&self, params: &SearchParams, page: usize) -> Result<SearchPage, CoreError> {
+        let challenges = match page {
+            1 => self.index.lock().await.search(params)?,
+            _ => Vec::new(),
+        };
+        Ok(SearchPage {
+            challenges,
+            page,
+            has_next: false,
+            issues: Vec::new(),
+            unavailable: Vec::new(),
+        })
+    }
+
+    async fn challenge(&self, url: &str) -> Result<Challenge, CoreError> {
+        self.index
+            .lock()
+            .await
+            .challenge(url)
+            .ok_or_else(|| CoreError::UnknownChallenge(url.to_string()))
+    }
+
+    async fn details(&self, url: &str) -> Result<ChallengeDetails, CoreError> {
+        Ok(ChallengeDetails {
+            description: self.index.lock().await.description(url),
+            ..ChallengeDetails::default()
+        })
+    }
+
+    async fn download(
+        &self,
+        challenge: &Challenge,
+        destination: &Path,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        cancelled: &AtomicBool,
+    ) -> Result<String, CoreError> {
+        self.site
+            .download(challenge, destination, progress, cancelled)
+            .await
+    }
+
+    async fn writeup_text(
+        &self,
+        challenge_url: &str,
+        writeup: &Writeup,
+    ) -> Result<String, CoreError> {
+        self.site.writeup_text(challenge_url, writeup).await
+    }
+
+    async fn download_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        self.site
+            .download_writeup(challenge, writeup, directory)
+            .await
+    }
+}
+
+/// A directory made by `cracked mirror`, browsed without any network access.
+/// Challenges missing from it cannot be downloaded.
+pub struct MirrorSource {
+    mirror: Mirror,
+    index: IndexSource,
+}
+
+impl MirrorSource {
+    pub fn open(directory: &Path) -> Result<Self, CoreError> {
+        let mirror = Mirror::open(directory)?;
+        let index = IndexSource::new(mirror.index()?);
+        Ok(Self { mirror, index })
+    }
+}
+
+#[async_trait]
+impl ChallengeSource for MirrorSource {
+    fn name(&self) -> &str {
+        "mirror"
+    }
+
+    fn owns(&self, url: &str) -> bool {
+        self.index.owns(url)
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    async fn search(&self, params: &SearchParams, page: usize) -> Result<SearchPage, CoreError> {
+        self.index.search(params, page).await
+    }
+
+    async fn challenge(&self, url: &str) -> Result<Challenge, CoreError> {
+        self.index.challenge(url).await
+    }
+
+    // Challenges still pending in the mirror only have their indexed description
+    async fn details(&self, url: &str) -> Result<ChallengeDetails, CoreError> {
+        match self.mirror.details(url) {
+            Some(details) => Ok(details),
+            None => self.index.details(url).await,
+        }
+    }
+
+    async fn download(
+        &self,
+        challenge: &Challenge,
+        destination: &Path,
+        progress: &(dyn Fn(u64, Option<u64>) + Sync),
+        _cancelled: &AtomicBool,
+    ) -> Result<String, CoreError> {
+        let archive = self.mirror.archive_path(&challenge.url);
+        if !archive.exists() {
+            return Err(CoreError::NotMirrored(challenge.url.clone()));
+        }
+        let copied = tokio::fs::copy(&archive, destination).await?;
+        progress(copied, Some(copied));
+        file_sha256(destination).await
+    }
+
+    async fn writeup_text(
+        &self,
+        challenge_url: &str,
+        writeup: &Writeup,
+    ) -> Result<String, CoreError> {
+        self.mirror.writeup_text(challenge_url, writeup)
+    }
+
+    async fn download_writeup(
+        &self,
+        challenge: &Challenge,
+        writeup: &Writeup,
+        directory: &Path,
+    ) -> Result<PathBuf, CoreError> {
+        self.mirror.copy_writeup(challenge, writeup, directory)
+    }
+}
+
+/// Every configured source, crackmes.one or what stands for it first.
+pub struct Sources {
+    sources: Vec<Box<dyn ChallengeSource>>,
+}
+
+impl Sources {
+    pub fn new(sources: Vec<Box<dyn ChallengeSource>>) -> Self {
+        Self { sources }
+    }
+
+    /// crackmes.one and the local directories of the configuration.
+    pub fn from_config(config: &Config) -> Result<Self, CoreError> {
+        Self::with_site(Box::new(CrackmesOne::default()), config)
+    }
+
+    /// `site` in place of crackmes.one, then the local directories of the
+    /// configuration.
+    pub fn with_site(site: Box<dyn ChallengeSource>, config: &Config) -> Result<Self, CoreError> {
+        let mut sources = vec![site];
+        for local in &config.sources {
+            sources.push(Box::new(LocalDirectory::open(&local.name, &local.path)?));
+        }
+        Ok(Self::new(sources))
+    }
+
+    /// The source a challenge URL belongs to, the first one if none claims it.
+    pub fn for_url(&self, url: &str) -> &dyn ChallengeSource {
+        self.sources
+            .iter()
+            .find(|source| source.owns(url))
+            .unwrap_or(&self.sources[0])
+            .as_ref()
+    }
+
+    pub fn cursor(&self, params: SearchParams) -> SourceCursor<'_> {
+        SourceCursor {
+            sources: self,
+            params,
+            next_page: 1,
+            exhausted: vec![false; self.sources.len()],
+        }
+    }
+
+    /// Runs a search and merges every result page into one, up to `max_pages`
+    /// if given.
+    pub async fn search(
+        &self,
+        params: SearchParams,
+        max_pages: Option<usize>,
+    ) -> Result<SearchPage, CoreError> {
+        let mut cursor = self.cursor(params);
+        let mut results = SearchPage {
+            challenges: Vec::new(),
+            page: 0,
+            has_next: true,
+            issues: Vec::new(),
+            unavailable: Vec::new(),
+        };
+        while results.has_next && max_pages.is_none_or(|max| results.page < max) {
+            let Some(page) = cursor.next_page().await? else {
+                results.has_next = false;
+                break;
+            };
+            results.challenges.extend(page.challenges);
+            results.page = page.page;
+            results.has_next = page.has_next;
+            // The same layout problem usually shows on every page
+            for issue in page.issues {
+                if !results.issues.contains(&issue) {
+                    results.issues.push(issue);
+                }
+            }
+            results.unavailable.extend(page.unavailable);
+        }
+        Ok(results)
+    }
+}
+
+/// Walks the results of every source at once, page by page: page `n` holds
+/// page `n` of each source that still has results.
+pub struct SourceCursor<'a> {
+    sources: &'a Sources,
+    params: SearchParams,
+    next_page: usize,
+    exhausted: Vec<bool>,
+}
+
+impl SourceCursor<'_> {
+    /// Number of pages fetched so far.
+    pub fn pages_loaded(&self) -> usize {
+        self.next_page - 1
+    }
+
+    /// Fetches the next page, or returns `None` once every source is exhausted.
+    /// A source that fails is left out of this page and the following ones,
+    /// unless every source failed, then the first error is returned.
+    pub async fn next_page(&mut self) -> Result<Option<SearchPage>, CoreError> {
+        if self.exhausted.iter().all(|&exhausted| exhausted) {
+            return Ok(None);
+        }
+
+        let mut challenges = Vec::new();
+        let mut issues = Vec::new();
+        let mut failed = Vec::new();
+        let mut searched = 0;
+        for (index, source) in self.sources.sources.iter().enumerate() {
+            if self.exhausted[index] {
+                continue;
+            }
+            let page = match source.search(&self.params, self.next_page).await {
+                Ok(page) => page,
+                Err(error) => {
+                    failed.push((index, error));
+                    continue;
+                }
+            };
+            searched += 1;
+            if !page.has_next || page.challenges.is_empty() {
+                self.exhausted[index] = true;
+            }
+            challenges.extend(page.challenges);
+            issues.extend(page.issues);
+        }
+        // Nothing to show, so the same page can be tried again
+        if searched == 0 && !failed.is_empty() {
+            return Err(failed.remove(0).1);
+        }
+
+        let mut unavailable = Vec::new();
+        for (index, error) in failed {
+            self.exhausted[index] = true;
+            unavailable.push(format!("{}: {}", self.sources.sources[index].name(), error));
+        }
+
+        let page = self.next_page;
+        self.next_page += 1;
+        Ok(Some(SearchPage {
+            challenges,
+            page,
+            has_next: self.exhausted.contains(&false),
+            issues,
+            unavailable,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::SearchArgs, mock_server::MockServer};
+
+    fn params() -> SearchParams {
+        SearchParams {
+            name: None,
+            author: None,
+            difficulty_range: Some([1, 6]),
+            quality_range: None,
+            language: None,
+            arch: None,
+            platform: None,
+            query: None,
+            token: String::new(),
+        }
+    }
+
+    fn team_directory() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("keygen.zip"), b"keygen").unwrap();
+        fs::write(
+            dir.path().join("keygen.toml"),
+            r#"
+                name = "Internal keygen"
+                author = "alice"
+                arch = "x86-64"
+                difficulty = 2.5
+                description = "Onboarding"
+                writeups = [{ author = "bob", file = "keygen.md" }]
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("keygen.md"), "# Solution").unwrap();
+        fs::write(dir.path().join("bare.zip"), b"bare").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_local_directory_reads_sidecars() {
+        let dir = team_directory();
+        let local = LocalDirectory::open("team", dir.path()).unwrap();
+
+        let page = local.search(&params(), 1).await.unwrap();
+        let names: Vec<&str> = page.challenges.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["bare", "Internal keygen"]);
+        assert!(!page.has_next);
+        assert!(
+            local
+                .search(&params(), 2)
+                .await
+                .unwrap()
+                .challenges
+                .is_empty()
+        );
+
+        let mut filtered = params();
+        filtered.difficulty_range = Some([2, 3]);
+        let page = local.search(&filtered, 1).await.unwrap();
+        assert_eq!(page.challenges.len(), 1);
+
+        let keygen = &page.challenges[0];
+        assert_eq!(keygen.url, "local://team/keygen");
+        let details = local.details(&keygen.url).await.unwrap();
+        assert_eq!(details.description.as_deref(), Some("Onboarding"));
+        let text = local
+            .writeup_text(&keygen.url, &details.writeup_links[0])
+            .await
+            .unwrap();
+        assert_eq!(text, "# Solution");
+    }
+
+    #[tokio::test]
+    async fn test_default_search_lists_unrated_local_challenges() {
+        let dir = team_directory();
+        let local = LocalDirectory::open("team", dir.path()).unwrap();
+
+        let params = SearchArgs::default().to_search_params(String::new());
+        let page = local.search(&params, 1).await.unwrap();
+        assert_eq!(page.challenges.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_local_writeups_stay_inside_the_directory() {
+        let dir = team_directory();
+        let local = LocalDirectory::open("team", dir.path()).unwrap();
+        let escape = Writeup {
+            author: String::from("mallory"),
+            url: String::from("local://team/../secret.md"),
+        };
+        assert!(
+            local
+                .writeup_text("local://team/keygen", &escape)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crackmes_one_searches_every_page_with_one_session() {
+        let server = MockServer::crackmes().await;
+        let sources = Sources::new(vec![Box::new(CrackmesOne::with_base_url(&server.url()))]);
+
+        let results = sources.search(params(), None).await.unwrap();
+        assert!(results.issues.is_empty(), "{:?}", results.issues);
+        let challenges = results.challenges;
+        assert_eq!(challenges.len(), 3);
+        assert_eq!(challenges[0].writeups, Some(1));
+        let token_requests = server
+            .requests()
+            .iter()
+            .filter(|request| request.method == "GET")
+            .count();
+        assert_eq!(token_requests, 1);
+
+        let source = sources.for_url(&challenges[0].url);
+        let details = source.details(&challenges[0].url).await.unwrap();
+        let text = source
+            .writeup_text(&challenges[0].url, &details.writeup_links[0])
+            .await
+            .unwrap();
+        assert!(text.contains("0x401000"));
+    }
+
+    #[tokio::test]
+    async fn test_local_sources_survive_a_failing_site() {
+        let server = MockServer::start(Vec::new()).await;
+        let dir = team_directory();
+        let sources = Sources::new(vec![
+            Box::new(CrackmesOne::with_base_url(&server.url())),
+            Box::new(LocalDirectory::open("team", dir.path()).unwrap()),
+        ]);
+
+        let page = sources.cursor(params()).next_page().await.unwrap().unwrap();
+        assert_eq!(page.challenges.len(), 2);
+        assert_eq!(page.unavailable.len(), 1);
+        assert!(page.unavailable[0].starts_with("crackmes.one: "));
+        assert!(!page.has_next);
+
+        let site_only = Sources::new(vec![Box::new(CrackmesOne::with_base_url(&server.url()))]);
+        assert!(site_only.cursor(params()).next_page().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_copies_the_archive() {
+        let dir = team_directory();
+        let output = tempfile::tempdir().unwrap();
+        let sources = Sources::new(vec![
+            Box::new(CrackmesOne::default()),
+            Box::new(LocalDirectory::open("team", dir.path()).unwrap()),
+        ]);
+
+        assert_eq!(sources.for_url("/crackme/abc").name(), "crackmes.one");
+        let source = sources.for_url("local://team/keygen");
+        assert_eq!(source.name(), "team");
+
+        let challenge = source.challenge("local://team/keygen").await.unwrap();
+        let destination = output.path().join("keygen.zip");
+        let sha256 = source
+            .download(
+                &challenge,
+                &destination,
+                &|_, _| {},
+                &AtomicBool::new(false),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&destination).unwrap(), b"keygen");
+        assert_eq!(sha256, file_sha256(&destination).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mirror_is_browsed_without_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let mirror = Mirror::open(dir.path()).unwrap();
+        let mut index = mirror.index().unwrap();
+        let (done, todo) = (Challenge::sample("done"), Challenge::sample("todo"));
+        mirror
+            .record(&mut index, &[done.clone(), todo.clone()])
+            .unwrap();
+        index.set_description(&todo.url, "Pending").unwrap();
+
+        let writeup = Writeup {
+            author: String::from("alice"),
+            url: String::from("/static/writeup/abc.md"),
+        };
+        let details = ChallengeDetails {
+            description: Some(String::from("Mirrored")),
+            writeup_links: vec![writeup.clone()],
+            ..ChallengeDetails::default()
+        };
+        let directory = dir.path().join("challenges/done");
+        fs::write(
+            directory.join("details.json"),
+            serde_json::to_string(&details).unwrap(),
+        )
+        .unwrap();
+        fs::write(mirror.archive_path(&done.url), b"archive").unwrap();
+        fs::create_dir_all(directory.join("writeups")).unwrap();
+        fs::write(mirror.writeup_path(&done.url, &writeup), "# Solution").unwrap();
+
+        let sources = Sources::new(vec![Box::new(MirrorSource::open(dir.path()).unwrap())]);
+        let source = sources.for_url(&done.url);
+        assert_eq!(source.name(), "mirror");
+        assert_eq!(
+            source.search(&params(), 1).await.unwrap().challenges.len(),
+            2
+        );
+        assert!(matches!(
+            source.challenge("/crackme/missing").await,
+            Err(CoreError::UnknownChallenge(_))
+        ));
+        let pending = source.details(&todo.url).await.unwrap();
+        assert_eq!(pending.description.as_deref(), Some("Pending"));
+        let mirrored = source.details(&done.url).await.unwrap();
+        let text = source
+            .writeup_text(&done.url, &mirrored.writeup_links[0])
+            .await
+            .unwrap();
+        assert_eq!(text, "# Solution");
+
+        let destination = dir.path().join("done.zip");
+        let cancelled = AtomicBool::new(false);
+        let sha256 = source
+            .download(&done, &destination, &|_, _| {}, &cancelled)
+            .await
+            .unwrap();
+        assert_eq!(sha256, file_sha256(&destination).await.unwrap());
+        let missing = source
+            .download(&todo, &dir.path().join("todo.zip"), &|_, _| {}, &cancelled)
+            .await;
+        assert!(matches!(missing, Err(CoreError::NotMirrored(_))));
+    }
+}
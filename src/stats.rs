@@ -0,0 +1,212 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::progress::{ChallengeProgress, ProgressStatus, ProgressStore};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Difficulty bands of the histogram, the site rates challenges from 1 to 6.
+pub const DIFFICULTY_BANDS: usize = 6;
+/// Days covered by the daily solves sparkline.
+pub const ACTIVITY_DAYS: usize = 30;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DifficultyBand {
+    pub solved: u64,
+    pub attempted: u64,
+    /// Median time spent on the solved challenges of the band, in seconds.
+    pub median_time_to_solve: Option<u64>,
+}
+
+/// Statistics derived from the progress store.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub solved: u64,
+    pub attempted: u64,
+    /// Solves per label, most solved first.
+    pub by_language: Vec<(String, u64)>,
+    pub by_arch: Vec<(String, u64)>,
+    pub by_platform: Vec<(String, u64)>,
+    /// Bands `[1, 2)`, `[2, 3)`, ... `[6, 6]`.
+    pub difficulty: [DifficultyBand; DIFFICULTY_BANDS],
+    /// Solves of each of the last days, oldest first and today last.
+    pub daily_solves: Vec<u64>,
+    /// Days in a row with a solve, ending today or yesterday.
+    pub current_streak: u64,
+    pub longest_streak: u64,
+}
+
+impl Stats {
+    /// Computes the statistics as of `now`. Days are counted in UTC.
+    pub fn compute(store: &ProgressStore, now: u64) -> Self {
+        let mut stats = Stats::default();
+        let mut by_language = HashMap::new();
+        let mut by_arch = HashMap::new();
+        let mut by_platform = HashMap::new();
+        let mut times: [Vec<u64>; DIFFICULTY_BANDS] = Default::default();
+        let mut solve_days = BTreeSet::new();
+        let today = now / SECONDS_PER_DAY;
+        stats.daily_solves = vec![0; ACTIVITY_DAYS];
+
+        for entry in store.entries() {
+            let band = difficulty_band(entry.challenge.difficulty);
+            if is_attempted(entry) {
+                stats.attempted += 1;
+                stats.difficulty[band].attempted += 1;
+            }
+            if entry.status != ProgressStatus::Solved {
+                continue;
+            }
+
+            stats.solved += 1;
+            stats.difficulty[band].solved += 1;
+            times[band].push(entry.time_spent_at(now));
+            *by_language
+                .entry(entry.challenge.language.to_string())
+                .or_default() += 1;
+            *by_arch.entry(entry.challenge.arch.to_string()).or_default() += 1;
+            *by_platform
+                .entry(entry.challenge.platform.to_string())
+                .or_default() += 1;
+
+            if let Some(solved_at) = solved_at(entry) {
+                let day = solved_at / SECONDS_PER_DAY;
+                solve_days.insert(day);
+                let age = today.saturating_sub(day) as usize;
+                if age < ACTIVITY_DAYS {
+                    stats.daily_solves[ACTIVITY_DAYS - 1 - age] += 1;
+                }
+            }
+        }
+
+        for (band, mut band_times) in stats.difficulty.iter_mut().zip(times) {
+            band.median_time_to_solve = median(&mut band_times);
+        }
+        stats.by_language = ranked(by_language);
+        stats.by_arch = ranked(by_arch);
+        stats.by_platform = ranked(by_platform);
+        (stats.current_streak, stats.longest_streak) = streaks(&solve_days, today);
+        stats
+    }
+}
+
+/// Index of the histogram band of a difficulty rating.
+pub fn difficulty_band(difficulty: f32) -> usize {
+    (difficulty.floor().max(1.0) as usize - 1).min(DIFFICULTY_BANDS - 1)
+}
+
+// Anything we actually worked on, downloading alone does not count
+fn is_attempted(entry: &ChallengeProgress) -> bool {
+    entry.history.iter().any(|change| {
+        matches!(
+            change.status,
+            ProgressStatus::InProgress | ProgressStatus::Solved | ProgressStatus::GaveUp
+        )
+    })
+}
+
+fn solved_at(entry: &ChallengeProgress) -> Option<u64> {
+    entry
+        .history
+        .iter()
+        .rev()
+        .find(|change| change.status == ProgressStatus::Solved)
+        .map(|change| change.at)
+}
+
+fn ranked(counts: HashMap<String, u64>) -> Vec<(String, u64)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_label, a), (b_label, b)| b.cmp(a).then_with(|| a_label.cmp(b_label)));
+    counts
+}
+
+fn median(values: &mut [u64]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[middle - 1] + values[middle]) / 2)
+    } else {
+        Some(values[middle])
+    }
+}
+
+// Returns the current and the longest runs of consecutive solve days. The
+// current streak survives until the end of the day after the last solve.
+fn streaks(days: &BTreeSet<u64>, today: u64) -> (u64, u64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+
+    for &day in days {
+        run = if previous.map(|previous| previous + 1) == Some(day) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let current = match previous {
+        Some(last) if last + 1 >= today => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{challenge::Challenge, search::Arch};
+
+    fn challenge(id: &str, difficulty: f32, arch: Arch) -> Challenge {
+        Challenge {
+            arch,
+            difficulty,
+            ..Challenge::sample(id)
+        }
+    }
+
+    #[test]
+    fn test_compute_counts_solves_and_attempts() {
+        let day = SECONDS_PER_DAY;
+        let mut store = ProgressStore::default();
+        let easy = challenge("easy", 1.5, Arch::X86);
+        let medium = challenge("medium", 2.2, Arch::X8664);
+        let hard = challenge("hard", 4.0, Arch::X8664);
+
+        store.set_status(&easy, ProgressStatus::InProgress, 10 * day);
+        store.set_status(&easy, ProgressStatus::Solved, 10 * day + 600);
+        store.set_status(&medium, ProgressStatus::InProgress, 11 * day);
+        store.set_status(&medium, ProgressStatus::Solved, 11 * day + 1800);
+        store.set_status(&hard, ProgressStatus::GaveUp, 11 * day);
+
+        let stats = Stats::compute(&store, 12 * day + 60);
+        assert_eq!((stats.solved, stats.attempted), (2, 3));
+        assert_eq!(stats.by_arch[0].1, 1);
+        assert_eq!(stats.by_language, vec![(String::from("C/C++"), 2)]);
+        assert_eq!(stats.difficulty[0].median_time_to_solve, Some(600));
+        assert_eq!(stats.difficulty[3].attempted, 1);
+        assert_eq!(stats.daily_solves[ACTIVITY_DAYS - 2], 1);
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 2));
+    }
+
+    #[test]
+    fn test_streaks_break_after_a_missed_day() {
+        let days = BTreeSet::from([1, 2, 3, 7, 8]);
+        assert_eq!(streaks(&days, 9), (2, 3));
+        assert_eq!(streaks(&days, 10), (0, 3));
+        assert_eq!(streaks(&BTreeSet::new(), 10), (0, 0));
+    }
+
+    #[test]
+    fn test_median_and_bands() {
+        assert_eq!(median(&mut [5, 1, 3]), Some(3));
+        assert_eq!(median(&mut [4, 1, 3, 2]), Some(2));
+        assert_eq!(median(&mut []), None);
+        assert_eq!(difficulty_band(0.5), 0);
+        assert_eq!(difficulty_band(3.9), 2);
+        assert_eq!(difficulty_band(6.0), 5);
+    }
+}
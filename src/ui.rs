@@ -3,10 +3,18 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Borders, Clear, List, ListItem, ListState, Paragraph,
+        Sparkline, Tabs, Wrap,
+    },
 };
 
-use crate::app::App;
+use crate::{
+    app::{App, DownloadStatus, LoggedError, Tab, WriteupStage},
+    form::SearchField,
+    progress::{self, ProgressStatus},
+    stats::{ACTIVITY_DAYS, Stats},
+};
 
 pub fn render(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
@@ -18,49 +26,262 @@ pub fn render(f: &mut Frame, app: &App) {
         ])
         .split(f.area());
 
-    render_title(f, chunks[0]);
-    render_challenge_list(f, chunks[1], app);
+    render_title(f, chunks[0], app);
+    match app.tab {
+        Tab::Challenges => render_challenges(f, chunks[1], app),
+        Tab::Recommended => render_recommendations(f, chunks[1], app),
+        Tab::Paths => render_paths(f, chunks[1], app),
+        Tab::Stats => render_stats(f, chunks[1], app),
+        Tab::Errors => render_errors(f, chunks[1], app),
+    }
     render_status_bar(f, chunks[2], app);
+
+    if app.writeup_browser.is_some() {
+        render_writeups(f, centered_rect(80, 80, f.area()), app);
+    }
+    if app.show_search_form {
+        render_search_form(f, centered_rect(60, 60, f.area()), app);
+    }
+    if app.show_error_popup {
+        render_error_popup(f, centered_rect(70, 40, f.area()), app);
+    }
+}
+
+fn render_challenges(f: &mut Frame, area: Rect, app: &App) {
+    let content = if app.show_downloads && !app.downloads.is_empty() {
+        let height = app.downloads.len().min(6) as u16 + 2;
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(height)])
+            .split(area);
+        render_downloads(f, rows[1], app);
+        rows[0]
+    } else {
+        area
+    };
+
+    if app.show_details {
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(content);
+        render_challenge_list(f, panes[0], app);
+        render_details(f, panes[1], app);
+    } else {
+        render_challenge_list(f, content, app);
+    }
 }
 
-fn render_title(f: &mut Frame, area: Rect) {
-    let title_text = vec![Line::from(Span::styled(
+fn render_title(f: &mut Frame, area: Rect, app: &App) {
+    let title = Line::from(Span::styled(
         "Crackmes.one Challenge Browser",
         Style::default()
             .fg(Color::Cyan)
             .add_modifier(Modifier::BOLD),
-    ))];
+    ));
+    let selected = Tab::ALL.iter().position(|&tab| tab == app.tab);
 
-    let title = Paragraph::new(title_text)
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
-    f.render_widget(title, area);
+    let tabs = Tabs::new(Tab::ALL.iter().map(|tab| tab.title()))
+        .select(selected)
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_alignment(Alignment::Center)
+                .title_bottom(Line::from(" Tab: Switch view ").right_aligned()),
+        );
+    f.render_widget(tabs, area);
 }
 
 fn render_challenge_list(f: &mut Frame, area: Rect, app: &App) {
-    let items: Vec<ListItem> = app
-        .challenges
-        .iter()
+    let ratings = &app.ratings;
+    let mut items: Vec<ListItem> = app
+        .visible_challenges()
         .map(|challenge| {
             let content = format!(
-                "{:<30} | {:>4.1} | {:>4.1} | {:<15} | {:<8} | {:<15}",
+                "{:<30} | {:>4.1} (you {:>3.1}) | {:>4.1} | {:<15} | {:<8} | {:<15}",
                 truncate(&challenge.name, 30),
                 challenge.difficulty,
+                ratings.expected_difficulty(challenge),
                 challenge.quality,
                 format!("{:?}", challenge.language),
                 format!("{:?}", challenge.arch),
                 format!("{:?}", challenge.platform),
             );
 
-            ListItem::new(Line::from(Span::raw(content)))
+            let status = app.progress.status(&challenge.url);
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{} ", status.marker()),
+                    Style::default().fg(progress_color(status)),
+                ),
+                Span::raw(content),
+            ]))
         })
         .collect();
 
+    if app.has_more_pages {
+        items.push(ListItem::new(Line::from(Span::styled(
+            "   ... more results on the next page",
+            Style::default().fg(Color::DarkGray),
+        ))));
+    }
+
+    let mut title = String::from(
+        "Challenges (↑/↓: Navigate, Enter: Download, i: Details, w: Writeups, s: Search, /: Filter, 1-5: Sort, r/R: Random, p/x/g/n: In progress/Solved/Gave up/New, q: Quit)",
+    );
+    if !app.filter.is_empty() {
+        title.push_str(&format!(" [filter: {}]", app.filter));
+    }
+    if let Some(sort) = app.sort {
+        let arrow = if sort.descending { "↓" } else { "↑" };
+        title.push_str(&format!(" [sort: {} {}]", sort.key.label(), arrow));
+    }
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.selected_index));
+
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn progress_color(status: ProgressStatus) -> Color {
+    match status {
+        ProgressStatus::New => Color::Reset,
+        ProgressStatus::Downloaded => Color::Blue,
+        ProgressStatus::InProgress => Color::Yellow,
+        ProgressStatus::Solved => Color::Green,
+        ProgressStatus::GaveUp => Color::Red,
+    }
+}
+
+fn render_recommendations(f: &mut Frame, area: Rect, app: &App) {
+    let (recommender, recommendations) = app.recommendations();
+    let items: Vec<ListItem> = if recommendations.is_empty() {
+        vec![ListItem::new(
+            "Nothing to recommend among the loaded challenges, search for more with s",
+        )]
+    } else {
+        recommendations
+            .iter()
+            .map(|recommendation| {
+                let challenge = &app.challenges[recommendation.index];
+                ListItem::new(format!(
+                    "{:>3.0}% | {:<30} | {:>4.1} | {:>4.1} | {:<8} | {}",
+                    recommendation.score * 100.0,
+                    truncate(&challenge.name, 30),
+                    challenge.difficulty,
+                    challenge.quality,
+                    challenge.arch.to_string(),
+                    challenge.author
+                ))
+            })
+            .collect()
+    };
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Recommended around difficulty {:.1}, comfort level {:.1} (↑/↓: Navigate, Enter: Show in list)",
+            recommender.target, recommender.comfort_level
+        )))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut list_state = ListState::default();
+    if !recommendations.is_empty() {
+        list_state.select(Some(
+            app.selected_recommendation.min(recommendations.len() - 1),
+        ));
+    }
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_paths(f: &mut Frame, area: Rect, app: &App) {
+    let mut items = Vec::new();
+    let mut selected = None;
+    let mut stage_row = 0;
+
+    for path in &app.paths {
+        let progress = path.progress(&app.progress);
+        let completed = progress.iter().filter(|stage| stage.is_complete()).count();
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(
+                path.name.clone(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " ({}/{} stages){}",
+                completed,
+                progress.len(),
+                path.description
+                    .as_ref()
+                    .map(|description| format!(" - {}", description))
+                    .unwrap_or_default()
+            )),
+        ])));
+
+        for (stage, stage_progress) in path.stages.iter().zip(progress) {
+            if stage_row == app.selected_stage {
+                selected = Some(items.len());
+            }
+            stage_row += 1;
+
+            let (marker, color) = if stage_progress.is_complete() {
+                ("✓", Color::Green)
+            } else if stage_progress.unlocked {
+                ("~", Color::Yellow)
+            } else {
+                ("🔒", Color::DarkGray)
+            };
+            let solved = stage_progress.solved.min(stage_progress.required);
+            let filled = solved * 10 / stage_progress.required;
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!(
+                    "  {} {:<30} [{}{}] {}/{} solved",
+                    marker,
+                    truncate(&stage.name, 30),
+                    "#".repeat(filled),
+                    "-".repeat(10 - filled),
+                    solved,
+                    stage_progress.required
+                ),
+                Style::default().fg(color),
+            ))));
+        }
+    }
+    if items.is_empty() {
+        items.push(ListItem::new(
+            "No learning paths, add TOML files to ~/.config/cracked/paths",
+        ));
+    }
+
     let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Challenges (↑/↓: Navigate, Enter: Download, q: Quit)"),
+                .title("Learning paths (↑/↓: Navigate, Enter: Load stage)"),
         )
         .highlight_style(
             Style::default()
@@ -71,16 +292,561 @@ fn render_challenge_list(f: &mut Frame, area: Rect, app: &App) {
         .highlight_symbol(">> ");
 
     let mut list_state = ListState::default();
-    list_state.select(Some(app.selected_index));
+    list_state.select(selected);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_stats(f: &mut Frame, area: Rect, app: &App) {
+    let stats = &app.stats;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Percentage(45),
+            Constraint::Min(8),
+            Constraint::Length(5),
+        ])
+        .split(area);
+
+    let rating = app.ratings.user;
+    let summary = Paragraph::new(format!(
+        "{} solved out of {} attempted | rating: {:.0} | current streak: {} days | longest streak: {} days",
+        stats.solved, stats.attempted, rating.value, stats.current_streak, stats.longest_streak
+    ))
+    .block(Block::default().borders(Borders::ALL).title("Summary"));
+    f.render_widget(summary, rows[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(rows[1]);
+    render_solve_counts(f, columns[0], "Solves per language", &stats.by_language);
+    render_solve_counts(f, columns[1], "Solves per arch", &stats.by_arch);
+    render_solve_counts(f, columns[2], "Solves per platform", &stats.by_platform);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[2]);
+    render_difficulty_histogram(f, columns[0], stats);
+    render_solve_times(f, columns[1], stats);
+
+    let activity = Sparkline::default()
+        .data(&stats.daily_solves)
+        .style(Style::default().fg(Color::Green))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Solves over the last {} days", ACTIVITY_DAYS)),
+        );
+    f.render_widget(activity, rows[3]);
+}
+
+fn render_solve_counts(f: &mut Frame, area: Rect, title: &str, counts: &[(String, u64)]) {
+    let bars: Vec<Bar> = counts
+        .iter()
+        .map(|(label, count)| {
+            Bar::default()
+                .label(Line::from(label.as_str()))
+                .value(*count)
+        })
+        .collect();
+
+    let chart = BarChart::default()
+        .direction(Direction::Horizontal)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(chart, area);
+}
+
+fn render_difficulty_histogram(f: &mut Frame, area: Rect, stats: &Stats) {
+    let mut chart = BarChart::default().bar_width(3).group_gap(2).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Difficulty: solved (green) vs attempted (yellow)"),
+    );
+    for (band, counts) in stats.difficulty.iter().enumerate() {
+        let label = format!("{}", band + 1);
+        chart = chart.data(
+            BarGroup::default().label(Line::from(label)).bars(&[
+                Bar::default()
+                    .value(counts.solved)
+                    .style(Style::default().fg(Color::Green)),
+                Bar::default()
+                    .value(counts.attempted)
+                    .style(Style::default().fg(Color::Yellow)),
+            ]),
+        );
+    }
+    f.render_widget(chart, area);
+}
+
+fn render_solve_times(f: &mut Frame, area: Rect, stats: &Stats) {
+    let lines: Vec<Line> = stats
+        .difficulty
+        .iter()
+        .enumerate()
+        .map(|(band, counts)| {
+            let median = counts
+                .median_time_to_solve
+                .map(progress::format_duration)
+                .unwrap_or_else(|| String::from("-"));
+            Line::from(format!("Difficulty {}: {}", band + 1, median))
+        })
+        .collect();
+
+    let times = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Median time to solve"),
+    );
+    f.render_widget(times, area);
+}
+
+fn render_errors(f: &mut Frame, area: Rect, app: &App) {
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let now = progress::now();
+    let items: Vec<ListItem> = if app.errors.is_empty() {
+        vec![ListItem::new("No errors so far")]
+    } else {
+        app.errors
+            .iter()
+            .map(|error| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:>8} ago ",
+                            progress::format_duration(now.saturating_sub(error.time))
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!("{}: ", error.context),
+                        Style::default().fg(Color::Red),
+                    ),
+                    Span::raw(error.message.clone()),
+                ]))
+            })
+            .collect()
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Errors, newest first (↑/↓: Navigate)"),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut list_state = ListState::default();
+    if !app.errors.is_empty() {
+        list_state.select(Some(app.selected_error.min(app.errors.len() - 1)));
+    }
+    f.render_stateful_widget(list, panes[0], &mut list_state);
+
+    let lines = app
+        .errors
+        .get(app.selected_error)
+        .map(error_lines)
+        .unwrap_or_default();
+    let details = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Details"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, panes[1]);
+}
+
+fn render_error_popup(f: &mut Frame, area: Rect, app: &App) {
+    let Some(error) = app.errors.front() else {
+        return;
+    };
+    let popup = Paragraph::new(error_lines(error))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title("Error (Esc: Dismiss, the Errors tab keeps a log)"),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+// The context, the message and the labelled details of a logged error
+fn error_lines(error: &LoggedError) -> Vec<Line<'_>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            error.context.as_str(),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(error.message.as_str()),
+        Line::from(""),
+    ];
+    lines.extend(error.details.iter().map(|(label, value)| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<9}", format!("{}:", label)),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(value.as_str()),
+        ])
+    }));
+    lines
+}
+
+fn render_details(f: &mut Frame, area: Rect, app: &App) {
+    let label = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", name), label),
+            Span::raw(value),
+        ])
+    };
+    let unknown = || String::from("-");
+
+    let lines = match (app.get_selected_challenge(), app.get_selected_details()) {
+        (Some(challenge), Some(details)) => {
+            let progress = app.progress.get(&challenge.url);
+            let status = progress
+                .map(|entry| {
+                    format!(
+                        "{} ({} spent)",
+                        entry.status.label(),
+                        progress::format_duration(entry.time_spent_at(progress::now()))
+                    )
+                })
+                .unwrap_or_else(|| String::from(ProgressStatus::New.label()));
+            let ratings = &app.ratings;
+            let mut lines = vec![
+                field("Author", challenge.author.clone()),
+                field("Status", status),
+                field(
+                    "Difficulty",
+                    format!(
+                        "{:.1} on the site, {:.1} for you (rating {:.0})",
+                        challenge.difficulty,
+                        ratings.expected_difficulty(challenge),
+                        ratings.user.value
+                    ),
+                ),
+                field(
+                    "Uploaded",
+                    details.upload_date.clone().unwrap_or_else(unknown),
+                ),
+                field(
+                    "Downloads",
+                    details
+                        .downloads
+                        .map(|d| d.to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                field(
+                    "Writeups",
+                    details
+                        .writeups
+                        .or(challenge.writeups)
+                        .map(|w| w.to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                field("Hash", details.hash.clone().unwrap_or_else(unknown)),
+                Line::from(""),
+                Line::from(Span::styled("Description", label)),
+                Line::from(
+                    details
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| String::from("No description")),
+                ),
+                Line::from(""),
+                Line::from(Span::styled(
+                    format!("Comments ({})", details.comments.len()),
+                    label,
+                )),
+            ];
+            for comment in &details.comments {
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", comment.author),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::raw(comment.text.clone()),
+                ]));
+            }
+            lines
+        }
+        (Some(challenge), None) if app.failed_details.contains(&challenge.url) => {
+            vec![Line::from(
+                "Could not fetch the challenge page, select it again to retry",
+            )]
+        }
+        (Some(_), None) => vec![Line::from("Loading details...")],
+        (None, _) => vec![Line::from("No challenge selected")],
+    };
+
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
+
+    f.render_widget(details, area);
+}
+
+fn render_downloads(f: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<ListItem> = app
+        .downloads
+        .iter()
+        .map(|item| {
+            let (state, color) = match &item.status {
+                DownloadStatus::Queued => (String::from("queued"), Color::DarkGray),
+                DownloadStatus::Downloading { attempt } => {
+                    let bar = progress_bar(item.downloaded, item.total, 20);
+                    let retry = if *attempt > 1 {
+                        format!(" (attempt {})", attempt)
+                    } else {
+                        String::new()
+                    };
+                    (format!("{}{}", bar, retry), Color::Yellow)
+                }
+                DownloadStatus::Done(summary) => (summary.clone(), Color::Green),
+                DownloadStatus::Failed => (String::from("failed"), Color::Red),
+                DownloadStatus::Cancelled => (String::from("cancelled"), Color::DarkGray),
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ", truncate(&item.name, 30))),
+                Span::styled(state, Style::default().fg(color)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Downloads (J/K: Select, c: Cancel, D: Hide)"),
+        )
+        .highlight_symbol("> ");
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.selected_download));
 
     f.render_stateful_widget(list, area, &mut list_state);
 }
 
+fn progress_bar(downloaded: u64, total: Option<u64>, width: usize) -> String {
+    let kib = downloaded / 1024;
+    match total {
+        Some(total) if total > 0 => {
+            let filled = (downloaded.min(total) * width as u64 / total) as usize;
+            format!(
+                "[{}{}] {:>3}% {} KiB",
+                "#".repeat(filled),
+                "-".repeat(width - filled),
+                downloaded.min(total) * 100 / total,
+                kib
+            )
+        }
+        _ => format!("{} KiB", kib),
+    }
+}
+
+fn render_writeups(f: &mut Frame, area: Rect, app: &App) {
+    let Some(browser) = app.writeup_browser.as_ref() else {
+        return;
+    };
+    f.render_widget(Clear, area);
+
+    match browser.stage {
+        WriteupStage::List => {
+            let writeups = app.get_writeups();
+            let items: Vec<ListItem> = if writeups.is_empty() {
+                let message = if app.details.contains_key(&browser.challenge_url) {
+                    "No writeups for this challenge yet"
+                } else if app.failed_details.contains(&browser.challenge_url) {
+                    "Could not fetch the challenge page, open the writeups again to retry"
+                } else {
+                    "Loading writeups..."
+                };
+                vec![ListItem::new(message)]
+            } else {
+                writeups
+                    .iter()
+                    .map(|writeup| ListItem::new(format!("Writeup by {}", writeup.author)))
+                    .collect()
+            };
+
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Writeups (Enter: Reveal, s: Download, q: Back)"),
+                )
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol(">> ");
+
+            let mut list_state = ListState::default();
+            if !writeups.is_empty() {
+                list_state.select(Some(browser.selected_index));
+            }
+            f.render_stateful_widget(list, area, &mut list_state);
+        }
+        WriteupStage::ConfirmReveal => {
+            let warning = Paragraph::new(vec![
+                Line::from(Span::styled(
+                    "This writeup contains the solution.",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )),
+                Line::from(""),
+                Line::from("Reveal the spoiler? (y: Reveal, any other key: Cancel)"),
+            ])
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL).title("Spoiler"));
+            f.render_widget(warning, area);
+        }
+        WriteupStage::Reading => {
+            let lines = match &browser.content {
+                Some(content) => render_markdown(content),
+                None => vec![Line::from("Loading writeup...")],
+            };
+            let writeup = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .scroll((browser.scroll, 0))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Writeup (↑/↓: Scroll, s: Download, q: Hide)"),
+                );
+            f.render_widget(writeup, area);
+        }
+    }
+}
+
+fn render_search_form(f: &mut Frame, area: Rect, app: &App) {
+    f.render_widget(Clear, area);
+    let form = &app.search_form;
+
+    let lines: Vec<Line> = SearchField::ALL
+        .iter()
+        .map(|&field| {
+            let focused = field == form.focused_field();
+            let value = match field {
+                _ if field.is_text() && focused => format!("{}_", form.value(field)),
+                _ if field.is_text() => form.value(field),
+                _ => format!("< {} >", form.value(field)),
+            };
+            let style = if focused {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<16}", field.label()),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(value, style),
+            ])
+        })
+        .collect();
+
+    let form = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Search (Tab/↑/↓: Field, ←/→: Change, Enter: Search, Esc: Cancel)"),
+    );
+    f.render_widget(form, area);
+}
+
+// Minimal markdown styling: headings, code blocks and list bullets.
+fn render_markdown(content: &str) -> Vec<Line<'_>> {
+    let mut in_code_block = false;
+
+    content
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return Line::from(Span::styled(line, Style::default().fg(Color::DarkGray)));
+            }
+            if in_code_block {
+                return Line::from(Span::styled(line, Style::default().fg(Color::Green)));
+            }
+            if line.starts_with('#') {
+                return Line::from(Span::styled(
+                    line.trim_start_matches('#').trim(),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            let item = line.trim_start();
+            if let Some(text) = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|marker| item.strip_prefix(marker))
+            {
+                return Line::from(vec![
+                    Span::raw(&line[..line.len() - item.len()]),
+                    Span::styled("• ", Style::default().fg(Color::Yellow)),
+                    Span::raw(text),
+                ]);
+            }
+            Line::from(line)
+        })
+        .collect()
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
 fn render_status_bar(f: &mut Frame, area: Rect, app: &App) {
-    let status_text = if let Some(challenge) = app.get_selected_challenge() {
+    let status_text = if app.editing_filter {
+        format!("/{}_ (Enter: Keep filter, Esc: Clear)", app.filter)
+    } else if let Some(challenge) = app.get_selected_challenge() {
         format!(
-            "Selected: {} by {} | {}",
-            challenge.name, challenge.author, app.status_message
+            "Selected: {} by {} | {} | {}",
+            challenge.name,
+            challenge.author,
+            app.page_summary(),
+            app.status_message
         )
     } else {
         app.status_message.clone()
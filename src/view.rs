@@ -0,0 +1,181 @@
+use std::cmp::Ordering;
+
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+
+use crate::challenge::Challenge;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Difficulty,
+    Quality,
+    Name,
+    Language,
+    Date,
+}
+
+impl SortKey {
+    /// Sort key bound to a number key, in the order shown in the list title.
+    pub fn from_digit(digit: char) -> Option<Self> {
+        match digit {
+            '1' => Some(SortKey::Difficulty),
+            '2' => Some(SortKey::Quality),
+            '3' => Some(SortKey::Name),
+            '4' => Some(SortKey::Language),
+            '5' => Some(SortKey::Date),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Difficulty => "difficulty",
+            SortKey::Quality => "quality",
+            SortKey::Name => "name",
+            SortKey::Language => "language",
+            SortKey::Date => "date",
+        }
+    }
+
+    fn compare(self, a: &Challenge, b: &Challenge) -> Ordering {
+        match self {
+            SortKey::Difficulty => a.difficulty.total_cmp(&b.difficulty),
+            SortKey::Quality => a.quality.total_cmp(&b.quality),
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Language => a.language.to_string().cmp(&b.language.to_string()),
+            SortKey::Date => date_key(a).cmp(&date_key(b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+/// Indices into `challenges` of the entries matching `filter`, in `sort`
+/// order. Without a sort, matches keep the order of the search results.
+pub fn build_view(challenges: &[Challenge], filter: &str, sort: Option<SortOrder>) -> Vec<usize> {
+    let matcher = SkimMatcherV2::default();
+    let filter = filter.trim();
+
+    let mut view: Vec<usize> = challenges
+        .iter()
+        .enumerate()
+        .filter(|(_, challenge)| {
+            filter.is_empty()
+                || matcher.fuzzy_match(&challenge.name, filter).is_some()
+                || matcher.fuzzy_match(&challenge.author, filter).is_some()
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    if let Some(sort) = sort {
+        // Stable, so ties keep the order of the search results
+        view.sort_by(|&a, &b| {
+            let ordering = sort.key.compare(&challenges[a], &challenges[b]);
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    view
+}
+
+// Challenges without a date sort before every dated one
+fn date_key(challenge: &Challenge) -> Option<(u32, u32, u32, u32)> {
+    challenge.upload_date.as_deref().and_then(parse_date)
+}
+
+/// Reads both `2024-01-02 13:45` and `01:45 PM 01/02/2024` as (year, month,
+/// day, minutes) so that dates compare chronologically.
+pub fn parse_date(date: &str) -> Option<(u32, u32, u32, u32)> {
+    let mut day = None;
+    let mut time = (0, 0);
+    let mut afternoon = None;
+
+    for part in date.split_whitespace() {
+        if let [year, month, dd] = numbers(part, '-')[..] {
+            day = Some((year, month, dd));
+        } else if let [month, dd, year] = numbers(part, '/')[..] {
+            day = Some((year, month, dd));
+        } else if let [hours, minutes, ..] = numbers(part, ':')[..] {
+            time = (hours, minutes);
+        } else if part.eq_ignore_ascii_case("am") || part.eq_ignore_ascii_case("pm") {
+            afternoon = Some(part.eq_ignore_ascii_case("pm"));
+        }
+    }
+
+    let hours = match afternoon {
+        Some(false) => time.0 % 12,
+        Some(true) => time.0 % 12 + 12,
+        None => time.0,
+    };
+    day.map(|(year, month, dd)| (year, month, dd, hours * 60 + time.1))
+}
+
+fn numbers(part: &str, separator: char) -> Vec<u32> {
+    part.split(separator)
+        .map(|number| number.parse().ok())
+        .collect::<Option<Vec<_>>>()
+        .filter(|numbers| numbers.len() > 1)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(name: &str, author: &str, difficulty: f32, date: &str) -> Challenge {
+        Challenge {
+            author: String::from(author),
+            difficulty,
+            quality: 3.0,
+            upload_date: Some(String::from(date)),
+            ..Challenge::sample(name)
+        }
+    }
+
+    #[test]
+    fn test_build_view_filters_by_name_or_author() {
+        let challenges = vec![
+            challenge("easy_keygen", "alice", 1.0, "2024-01-01"),
+            challenge("vm_maze", "bob", 4.0, "2024-01-02"),
+            challenge("keyme", "carol", 2.0, "2024-01-03"),
+        ];
+
+        assert_eq!(build_view(&challenges, "kgen", None), vec![0]);
+        assert_eq!(build_view(&challenges, "bob", None), vec![1]);
+        assert_eq!(build_view(&challenges, "", None), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_build_view_sorts_matches() {
+        let challenges = vec![
+            challenge("a", "x", 3.0, "2024-01-01"),
+            challenge("b", "y", 1.0, "2023-06-01"),
+            challenge("c", "z", 2.0, "2024-02-01"),
+        ];
+        let sort = |key, descending| Some(SortOrder { key, descending });
+
+        assert_eq!(
+            build_view(&challenges, "", sort(SortKey::Difficulty, false)),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            build_view(&challenges, "", sort(SortKey::Date, true)),
+            vec![2, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_parse_date_orders_both_formats() {
+        assert_eq!(parse_date("2024-01-02 13:45"), Some((2024, 1, 2, 825)));
+        assert_eq!(parse_date("01:45 PM 01/02/2024"), Some((2024, 1, 2, 825)));
+        assert_eq!(parse_date("12:10 AM 01/02/2024"), Some((2024, 1, 2, 10)));
+        assert_eq!(parse_date("12:10 PM 01/02/2024"), Some((2024, 1, 2, 730)));
+        assert_eq!(parse_date("unknown"), None);
+    }
+}
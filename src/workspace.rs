@@ -0,0 +1,248 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    challenge::Challenge,
+    errors::CoreError,
+    output::ChallengeRecord,
+    paths::{ensure_within, sanitize_file_name, unique_path},
+};
+
+const METADATA_FILE: &str = "challenge.json";
+const ORIGINAL_DIR: &str = "original";
+
+// Templates shipped with cracked, overridable file by file
+const DEFAULT_TEMPLATES: [(&str, &str); 3] = [
+    ("notes.md", include_str!("../templates/notes.md")),
+    ("solve.py", include_str!("../templates/solve.py")),
+    ("keygen.rs", include_str!("../templates/keygen.rs")),
+];
+
+#[derive(Clone)]
+pub struct WorkspaceOptions {
+    /// Directory holding user templates, if any.
+    pub templates_dir: Option<PathBuf>,
+}
+
+impl WorkspaceOptions {
+    /// Templates live in `<config dir>/cracked/templates` unless told otherwise.
+    pub fn default_templates_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("cracked").join("templates"))
+    }
+}
+
+/// A directory laid out for working on a single challenge.
+pub struct Workspace {
+    pub directory: PathBuf,
+}
+
+impl Workspace {
+    /// Where the original archive and its extracted files go.
+    pub fn original_dir(&self) -> PathBuf {
+        self.directory.join(ORIGINAL_DIR)
+    }
+
+    /// Creates the workspace of `challenge` in `root`, or completes the one
+    /// left by a previous download. Files that already exist are left alone so
+    /// notes are never lost.
+    pub fn create(
+        root: &Path,
+        challenge: &Challenge,
+        options: &WorkspaceOptions,
+    ) -> Result<Self, CoreError> {
+        // A workspace of another challenge with the same name is not reused
+        let directory = unique_path(root, &challenge.name, challenge.id(), "", |path| {
+            workspace_id(path).is_none_or(|id| id == challenge.id())
+        });
+        ensure_within(root, &directory)?;
+
+        let workspace = Workspace { directory };
+        let failure = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| CoreError::WorkspaceFailure { path, source }
+        };
+        let original = workspace.original_dir();
+        fs::create_dir_all(&original).map_err(failure(&original))?;
+
+        for (file_name, template) in load_templates(options) {
+            let path = workspace.directory.join(sanitize_file_name(&file_name));
+            if !path.exists() {
+                fs::write(&path, render_template(&template, challenge)).map_err(failure(&path))?;
+            }
+        }
+
+        let path = workspace.directory.join(METADATA_FILE);
+        let metadata = serde_json::to_string_pretty(&ChallengeRecord::from(challenge))
+            .map_err(std::io::Error::from)
+            .map_err(failure(&path))?;
+        fs::write(&path, metadata).map_err(failure(&path))?;
+
+        Ok(workspace)
+    }
+}
+
+// ID recorded in the metadata of an existing workspace
+fn workspace_id(directory: &Path) -> Option<String> {
+    let metadata = fs::read_to_string(directory.join(METADATA_FILE)).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&metadata).ok()?;
+    value["id"].as_str().map(str::to_string)
+}
+
+// Built-in templates, replaced or extended by the files of the user template
+// directory
+fn load_templates(options: &WorkspaceOptions) -> Vec<(String, String)> {
+    let mut templates: Vec<(String, String)> = DEFAULT_TEMPLATES
+        .iter()
+        .map(|(name, content)| (name.to_string(), content.to_string()))
+        .collect();
+
+    let Some(entries) = options
+        .templates_dir
+        .as_ref()
+        .and_then(|dir| fs::read_dir(dir).ok())
+    else {
+        return templates;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let (Some(name), Ok(content)) = (
+            path.file_name().and_then(|name| name.to_str()),
+            fs::read_to_string(&path),
+        ) else {
+            continue;
+        };
+
+        match templates.iter_mut().find(|(existing, _)| existing == name) {
+            Some(template) => template.1 = content,
+            None => templates.push((name.to_string(), content)),
+        }
+    }
+
+    templates
+}
+
+fn render_template(template: &str, challenge: &Challenge) -> String {
+    let record = ChallengeRecord::from(challenge);
+    let values = [
+        ("id", record.id.to_string()),
+        ("name", record.name.to_string()),
+        ("author", record.author.to_string()),
+        ("language", record.language),
+        ("arch", record.arch),
+        ("platform", record.platform),
+        ("difficulty", format!("{:.1}", record.difficulty)),
+        ("quality", format!("{:.1}", record.quality)),
+        ("url", record.url),
+    ];
+
+    // A single pass, so that values are never expanded in turn
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match values.iter().find(|(key, _)| *key == &after[..end]) {
+            Some((_, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 2..];
+            }
+            // Unknown placeholders are left as they are
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Language;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            language: Language::Rust,
+            name: String::from("easy_keygen"),
+            ..Challenge::sample("abc123")
+        }
+    }
+
+    #[test]
+    fn test_render_template_fills_challenge_fields() {
+        let rendered = render_template("{{name}} by {{author}} ({{arch}}) {{url}}", &challenge());
+        assert_eq!(
+            rendered,
+            "easy_keygen by bob (x86-64) https://crackmes.one/crackme/abc123"
+        );
+
+        let sneaky = Challenge {
+            name: String::from("{{url}}"),
+            ..challenge()
+        };
+        assert_eq!(
+            render_template("{{name}} {{unknown}} {{id", &sneaky),
+            "{{url}} {{unknown}} {{id"
+        );
+    }
+
+    #[test]
+    fn test_create_lays_out_workspace_and_keeps_notes() {
+        let root = tempfile::tempdir().unwrap();
+        let templates = tempfile::tempdir().unwrap();
+        fs::write(templates.path().join("solve.py"), "# custom {{id}}").unwrap();
+        fs::write(templates.path().join("gdbinit"), "file {{name}}").unwrap();
+        let options = WorkspaceOptions {
+            templates_dir: Some(templates.path().to_path_buf()),
+        };
+
+        let workspace = Workspace::create(root.path(), &challenge(), &options).unwrap();
+
+        let dir = &workspace.directory;
+        assert_eq!(dir, &root.path().join("easy_keygen"));
+        assert!(dir.join("original").is_dir());
+        assert!(dir.join("notes.md").exists());
+        assert!(dir.join("keygen.rs").exists());
+        assert!(dir.join("challenge.json").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("solve.py")).unwrap(),
+            "# custom abc123"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("gdbinit")).unwrap(),
+            "file easy_keygen"
+        );
+
+        // A second download must not clobber existing notes
+        fs::write(dir.join("notes.md"), "my notes").unwrap();
+        let again = Workspace::create(root.path(), &challenge(), &options).unwrap();
+        assert_eq!(&again.directory, dir);
+        assert_eq!(
+            fs::read_to_string(dir.join("notes.md")).unwrap(),
+            "my notes"
+        );
+    }
+
+    #[test]
+    fn test_create_does_not_reuse_workspace_of_namesake() {
+        let root = tempfile::tempdir().unwrap();
+        let options = WorkspaceOptions {
+            templates_dir: None,
+        };
+        let mut namesake = challenge();
+        namesake.url = String::from("/crackme/def456");
+
+        Workspace::create(root.path(), &challenge(), &options).unwrap();
+        let other = Workspace::create(root.path(), &namesake, &options).unwrap();
+        assert_eq!(other.directory, root.path().join("easy_keygen-def456"));
+    }
+}
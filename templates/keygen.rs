@@ -0,0 +1,14 @@
+//! Keygen for {{name}} by {{author}} ({{arch}}, {{platform}}).
+//!
+//! {{url}}
+//!
+//! Build with `rustc keygen.rs` and run `./keygen <username>`.
+
+fn keygen(username: &str) -> String {
+    username.to_string()
+}
+
+fn main() {
+    let username = std::env::args().nth(1).unwrap_or_default();
+    println!("{}", keygen(&username));
+}
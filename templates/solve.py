@@ -0,0 +1,13 @@
+#!/usr/bin/env python3
+"""Solver for {{name}} by {{author}} ({{arch}}, {{platform}}).
+
+{{url}}
+"""
+
+
+def solve():
+    pass
+
+
+if __name__ == "__main__":
+    solve()